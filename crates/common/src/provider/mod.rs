@@ -91,6 +91,8 @@ pub struct ProviderBuilder {
     /// JWT Secret
     jwt: Option<String>,
     headers: Vec<String>,
+    /// The proxy to route HTTP requests through
+    proxy: Option<Url>,
     is_local: bool,
 }
 
@@ -140,6 +142,7 @@ impl ProviderBuilder {
             compute_units_per_second: ALCHEMY_FREE_TIER_CUPS,
             jwt: None,
             headers: vec![],
+            proxy: None,
             is_local,
         }
     }
@@ -232,6 +235,13 @@ impl ProviderBuilder {
         self
     }
 
+    /// Sets the HTTP or SOCKS proxy to route HTTP requests through, instead of the one set in the
+    /// environment, if any.
+    pub fn proxy(mut self, proxy: Option<Url>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
     /// Constructs the `RetryProvider` taking all configs into account.
     pub fn build(self) -> Result<RetryProvider> {
         let Self {
@@ -243,6 +253,7 @@ impl ProviderBuilder {
             compute_units_per_second,
             jwt,
             headers,
+            proxy,
             is_local,
         } = self;
         let url = url?;
@@ -254,6 +265,7 @@ impl ProviderBuilder {
             .with_timeout(timeout)
             .with_headers(headers)
             .with_jwt(jwt)
+            .with_proxy(proxy)
             .build();
         let client = ClientBuilder::default().layer(retry_layer).transport(transport, is_local);
 
@@ -283,6 +295,7 @@ impl ProviderBuilder {
             compute_units_per_second,
             jwt,
             headers,
+            proxy,
             is_local,
         } = self;
         let url = url?;
@@ -294,6 +307,7 @@ impl ProviderBuilder {
             .with_timeout(timeout)
             .with_headers(headers)
             .with_jwt(jwt)
+            .with_proxy(proxy)
             .build();
 
         let client = ClientBuilder::default().layer(retry_layer).transport(transport, is_local);
@@ -80,6 +80,8 @@ pub struct RuntimeTransport {
     jwt: Option<String>,
     /// The timeout for requests.
     timeout: std::time::Duration,
+    /// The proxy to route HTTP requests through.
+    proxy: Option<Url>,
 }
 
 /// A builder for [RuntimeTransport].
@@ -89,12 +91,13 @@ pub struct RuntimeTransportBuilder {
     headers: Vec<String>,
     jwt: Option<String>,
     timeout: std::time::Duration,
+    proxy: Option<Url>,
 }
 
 impl RuntimeTransportBuilder {
     /// Create a new builder with the given URL.
     pub fn new(url: Url) -> Self {
-        Self { url, headers: vec![], jwt: None, timeout: REQUEST_TIMEOUT }
+        Self { url, headers: vec![], jwt: None, timeout: REQUEST_TIMEOUT, proxy: None }
     }
 
     /// Set the URL for the transport.
@@ -115,6 +118,15 @@ impl RuntimeTransportBuilder {
         self
     }
 
+    /// Set the HTTP or SOCKS proxy for the transport.
+    ///
+    /// Overrides the `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` environment variables, which are
+    /// used otherwise.
+    pub fn with_proxy(mut self, proxy: Option<Url>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
     /// Builds the [RuntimeTransport] and returns it in a disconnected state.
     /// The runtime transport will then connect when the first request happens.
     pub fn build(self) -> RuntimeTransport {
@@ -124,6 +136,7 @@ impl RuntimeTransportBuilder {
             headers: self.headers,
             jwt: self.jwt,
             timeout: self.timeout,
+            proxy: self.proxy,
         }
     }
 }
@@ -150,6 +163,13 @@ impl RuntimeTransport {
         let mut client_builder = reqwest::Client::builder()
             .timeout(self.timeout)
             .tls_built_in_root_certs(self.url.scheme() == "https");
+
+        // The credentials of an authenticated proxy are part of its URL.
+        if let Some(proxy) = self.proxy.clone() {
+            let proxy =
+                reqwest::Proxy::all(proxy).map_err(RuntimeTransportError::HttpConstructionError)?;
+            client_builder = client_builder.proxy(proxy);
+        }
         let mut headers = reqwest::header::HeaderMap::new();
 
         // If there's a JWT, add it to the headers if we can decode it.
@@ -175,6 +175,9 @@ pub struct EtherscanConfig {
     pub url: Option<String>,
     /// The etherscan API KEY that's required to make requests
     pub key: EtherscanApiKey,
+    /// The verification provider to use for this chain, e.g. `blockscout`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier: Option<String>,
 }
 
 impl EtherscanConfig {
@@ -188,7 +191,7 @@ impl EtherscanConfig {
         self,
         alias: Option<&str>,
     ) -> Result<ResolvedEtherscanConfig, EtherscanConfigError> {
-        let Self { chain, mut url, key } = self;
+        let Self { chain, mut url, key, verifier } = self;
 
         if let Some(url) = &mut url {
             *url = interpolate(url)?;
@@ -216,27 +219,36 @@ impl EtherscanConfig {
         };
         let key = key.resolve()?;
 
-        match (chain, url) {
-            (Some(chain), Some(api_url)) => Ok(ResolvedEtherscanConfig {
+        let mut resolved = match (chain, url) {
+            (Some(chain), Some(api_url)) => ResolvedEtherscanConfig {
                 api_url,
                 browser_url: chain.etherscan_urls().map(|(_, url)| url.to_string()),
                 key,
                 chain: Some(chain),
-            }),
-            (Some(chain), None) => ResolvedEtherscanConfig::create(key, chain).ok_or_else(|| {
-                let msg = alias.map(|a| format!(" `{a}`")).unwrap_or_default();
-                EtherscanConfigError::UnknownChain(msg, chain)
-            }),
-            (None, Some(api_url)) => {
-                Ok(ResolvedEtherscanConfig { api_url, browser_url: None, key, chain: None })
+                verifier: None,
+            },
+            (Some(chain), None) => {
+                ResolvedEtherscanConfig::create(key, chain).ok_or_else(|| {
+                    let msg = alias.map(|a| format!(" `{a}`")).unwrap_or_default();
+                    EtherscanConfigError::UnknownChain(msg, chain)
+                })?
             }
+            (None, Some(api_url)) => ResolvedEtherscanConfig {
+                api_url,
+                browser_url: None,
+                key,
+                chain: None,
+                verifier: None,
+            },
             (None, None) => {
                 let msg = alias
                     .map(|a| format!(" for Etherscan config with unknown alias `{a}`"))
                     .unwrap_or_default();
-                Err(EtherscanConfigError::MissingUrlOrChain(msg))
+                return Err(EtherscanConfigError::MissingUrlOrChain(msg))
             }
-        }
+        };
+        resolved.verifier = verifier;
+        Ok(resolved)
     }
 }
 
@@ -254,6 +266,9 @@ pub struct ResolvedEtherscanConfig {
     /// The chain name or EIP-155 chain ID.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub chain: Option<Chain>,
+    /// The verification provider to use for this chain, if configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier: Option<String>,
 }
 
 impl ResolvedEtherscanConfig {
@@ -266,6 +281,7 @@ impl ResolvedEtherscanConfig {
             browser_url: Some(browser_url.to_string()),
             key: api_key.into(),
             chain: Some(chain),
+            verifier: None,
         })
     }
 
@@ -294,7 +310,7 @@ impl ResolvedEtherscanConfig {
         self,
     ) -> Result<foundry_block_explorers::Client, foundry_block_explorers::errors::EtherscanError>
     {
-        let Self { api_url, browser_url, key: api_key, chain } = self;
+        let Self { api_url, browser_url, key: api_key, chain, .. } = self;
         let (mainnet_api, mainnet_url) = NamedChain::Mainnet.etherscan_urls().expect("exist; qed");
 
         let cache = chain
@@ -423,6 +439,7 @@ mod tests {
                 chain: Some(Mainnet.into()),
                 url: None,
                 key: EtherscanApiKey::Key("ABCDEFG".to_string()),
+                verifier: None,
             },
         );
 
@@ -440,6 +457,7 @@ mod tests {
                 chain: Some(Mainnet.into()),
                 url: Some("https://api.etherscan.io/api".to_string()),
                 key: EtherscanApiKey::Key("ABCDEFG".to_string()),
+                verifier: None,
             },
         );
 
@@ -458,6 +476,7 @@ mod tests {
                 chain: Some(Mainnet.into()),
                 url: Some("https://api.etherscan.io/api".to_string()),
                 key: EtherscanApiKey::Env(format!("${{{env}}}")),
+                verifier: None,
             },
         );
 
@@ -484,6 +503,7 @@ mod tests {
                 chain: None,
                 url: Some("https://api.etherscan.io/api".to_string()),
                 key: EtherscanApiKey::Key("ABCDEFG".to_string()),
+                verifier: None,
             },
         );
 
@@ -498,6 +518,7 @@ mod tests {
             chain: None,
             url: Some("https://api.etherscan.io/api".to_string()),
             key: EtherscanApiKey::Key("ABCDEFG".to_string()),
+            verifier: None,
         };
         let resolved = config.clone().resolve(Some("base_sepolia")).unwrap();
         assert_eq!(resolved.chain, Some(Chain::base_sepolia()));
@@ -505,4 +526,16 @@ mod tests {
         let resolved = config.resolve(Some("base-sepolia")).unwrap();
         assert_eq!(resolved.chain, Some(Chain::base_sepolia()));
     }
+
+    #[test]
+    fn resolve_etherscan_verifier() {
+        let config = EtherscanConfig {
+            chain: Some(Mainnet.into()),
+            url: None,
+            key: EtherscanApiKey::Key("ABCDEFG".to_string()),
+            verifier: Some("blockscout".to_string()),
+        };
+        let resolved = config.resolve(None).unwrap();
+        assert_eq!(resolved.verifier.as_deref(), Some("blockscout"));
+    }
 }
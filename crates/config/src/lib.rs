@@ -594,8 +594,13 @@ impl Config {
     ///
     /// This will merge various providers, such as env,toml,remappings into the figment.
     pub fn to_figment(self, providers: FigmentProviders) -> Figment {
+        self.to_figment_with_profile(providers, Self::selected_profile())
+    }
+
+    /// Same as [Self::to_figment], but for the given profile instead of the one selected by the
+    /// `FOUNDRY_PROFILE` environment variable.
+    pub fn to_figment_with_profile(self, providers: FigmentProviders, profile: Profile) -> Figment {
         let mut c = self;
-        let profile = Self::selected_profile();
         let mut figment = Figment::default().merge(DappHardhatDirProvider(&c.root.0));
 
         // merge global foundry.toml file
@@ -1411,6 +1416,12 @@ impl Config {
         Self::with_root(root).into()
     }
 
+    /// Same as [Self::figment_with_root], but for the given profile instead of the one selected
+    /// by the `FOUNDRY_PROFILE` environment variable.
+    pub fn figment_with_root_and_profile(root: impl Into<PathBuf>, profile: Profile) -> Figment {
+        Self::with_root(root).to_figment_with_profile(FigmentProviders::All, profile)
+    }
+
     /// Creates a new Config that adds additional context extracted from the provided root.
     ///
     /// # Example
@@ -2962,6 +2973,30 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_figment_with_root_and_profile() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "foundry.toml",
+                r"
+                [profile.default]
+                libs = ['lib']
+                optimizer_runs = 100
+                [profile.deploy]
+                optimizer_runs = 1000
+            ",
+            )?;
+            jail.set_env("FOUNDRY_PROFILE", "default");
+            let figment = Config::figment_with_root_and_profile(jail.directory(), "deploy".into());
+            assert_eq!(figment.profile(), "deploy");
+            let config = Config::from_provider(figment);
+            assert_eq!(config.optimizer_runs, 1000);
+            assert_eq!(config.libs, vec![PathBuf::from("lib")]);
+
+            Ok(())
+        });
+    }
+
     #[test]
     fn test_default_test_path() {
         figment::Jail::expect_with(|_| {
@@ -3307,6 +3342,7 @@ mod tests {
                             chain: Some(NamedChain::Mainnet.into()),
                             browser_url: Some(mainnet_urls.1.to_string()),
                             key: "FX42Z3BBJJEWXWGYV2X1CIPRSCN".to_string(),
+                            verifier: None,
                         }
                     ),
                     (
@@ -3316,6 +3352,7 @@ mod tests {
                             chain: Some(Moonbeam.into()),
                             browser_url: Some(mb_urls.1.to_string()),
                             key: "123456789".to_string(),
+                            verifier: None,
                         }
                     ),
                 ])
@@ -359,7 +359,7 @@ impl CreateArgs {
             show_standard_json_input: self.show_standard_json_input,
             guess_constructor_args: false,
         };
-        println!("Waiting for {} to detect contract deployment...", verify.verifier.verifier);
+        println!("Waiting for {} to detect contract deployment...", verify.verifier.verifier());
         verify.run().await
     }
 
@@ -1,11 +1,15 @@
 use foundry_compilers::artifacts::{BytecodeHash, EvmVersion};
 use foundry_config::Config;
 use foundry_test_utils::{
-    forgetest_async,
+    forgetest, forgetest_async,
     rpc::{next_etherscan_api_key, next_http_archive_rpc_endpoint},
+    str,
     util::OutputExt,
     TestCommand, TestProject,
 };
+use std::io::Write;
+
+const ADDRESS: &str = "0x0000000000000000000000000000000000000001";
 
 #[allow(clippy::too_many_arguments)]
 fn test_verify_bytecode(
@@ -286,6 +290,115 @@ forgetest_async!(can_ignore_runtime, |prj, cmd| {
     );
 });
 
+// The mode flags only make sense together with the ones they require.
+forgetest!(vb_mode_flags_require_runtime_only, |_prj, cmd| {
+    cmd.args(["verify-bytecode", ADDRESS, "Counter", "--no-fork"]).assert_failure().stderr_eq(
+        str![[r#"
+error: the following required arguments were not provided:
+  --runtime-only
+...
+"#]],
+    );
+
+    cmd.forge_fuse()
+        .args(["verify-bytecode", ADDRESS, "Counter", "--hash-only"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the following required arguments were not provided:
+  --runtime-only
+...
+"#]]);
+
+    cmd.forge_fuse()
+        .args(["verify-bytecode", ADDRESS, "Counter", "--runtime-only", "--hash-without-metadata"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the following required arguments were not provided:
+  --hash-only
+...
+"#]]);
+});
+
+forgetest!(vb_mode_flags_conflicts, |_prj, cmd| {
+    cmd.args(["verify-bytecode", ADDRESS, "Counter", "--runtime-only", "--ignore", "creation"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the argument '--runtime-only' cannot be used with '--ignore <BYTECODE_TYPE>'
+...
+"#]]);
+
+    cmd.forge_fuse()
+        .args([
+            "verify-bytecode",
+            ADDRESS,
+            "Counter",
+            "--runtime-only",
+            "--no-fork",
+            "--deployed-after",
+            "1",
+        ])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the argument '--no-fork' cannot be used with '--deployed-after <BLOCK>'
+...
+"#]]);
+
+    cmd.forge_fuse()
+        .args(["verify-bytecode", ADDRESS, "Counter", "--offline", "--proxy"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the argument '--offline' cannot be used with '--proxy'
+...
+"#]]);
+
+    cmd.forge_fuse()
+        .args(["verify-bytecode", "--serve", "--from-broadcast", "broadcast.json"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the argument '--serve' cannot be used with '--from-broadcast <PATH>'
+...
+"#]]);
+
+    cmd.forge_fuse()
+        .args(["verify-bytecode", "--serve", "--summary-json"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+error: the argument '--serve' cannot be used with '--summary-json'
+...
+"#]]);
+});
+
+// Outside of `--serve` and `--from-broadcast` the contract to verify must be given.
+forgetest!(vb_requires_address_and_contract, |_prj, cmd| {
+    cmd.args(["verify-bytecode"]).assert_failure().stderr_eq(str![[r#"
+error: the following required arguments were not provided:
+  <ADDRESS>
+  <CONTRACT>
+...
+"#]]);
+
+    cmd.forge_fuse()
+        .args(["verify-bytecode", "--from-broadcast", "missing.json"])
+        .assert_failure()
+        .stderr_eq(str![[r#"
+...
+failed to read from [..]missing.json[..]
+...
+"#]]);
+});
+
+forgetest!(vb_serve_reports_invalid_requests, |_prj, cmd| {
+    cmd.args(["verify-bytecode", "--serve"])
+        .stdin(|mut stdin| {
+            stdin.write_all(b"not a request\n\n").unwrap();
+        })
+        .assert_success()
+        .stdout_eq(str![[r#"
+{"error":"Invalid request: [..]"}
+
+"#]]);
+});
+
 // Test predeploy contracts
 // TODO: Add test utils for base such as basescan keys and alchemy keys.
 // WETH9 Predeploy
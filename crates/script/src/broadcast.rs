@@ -422,7 +422,7 @@ impl BundledState {
 
     pub fn verify_preflight_check(&self) -> Result<()> {
         for sequence in self.sequence.sequences() {
-            if self.args.verifier.verifier == VerificationProviderType::Etherscan &&
+            if self.args.verifier.verifier() == VerificationProviderType::Etherscan &&
                 self.script_config
                     .config
                     .get_etherscan_api_key(Some(sequence.chain.into()))
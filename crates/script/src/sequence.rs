@@ -263,7 +263,7 @@ impl ScriptSequence {
         verify.set_chain(config, self.chain.into());
 
         if verify.etherscan.has_key() ||
-            verify.verifier.verifier != VerificationProviderType::Etherscan
+            verify.verifier.verifier() != VerificationProviderType::Etherscan
         {
             trace!(target: "script", "prepare future verifications");
 
@@ -24,6 +24,7 @@ use foundry_config::{Chain, Config};
 use foundry_evm::constants::DEFAULT_CREATE2_DEPLOYER;
 use futures::FutureExt;
 use regex::Regex;
+use reqwest::Url;
 use semver::{BuildMetadata, Version};
 use std::{fmt::Debug, sync::LazyLock};
 
@@ -255,6 +256,21 @@ impl EtherscanVerificationProvider {
         verifier_url: Option<&str>,
         etherscan_key: Option<&str>,
         config: &Config,
+    ) -> Result<Client> {
+        self.proxied_client(chain, verifier_url, etherscan_key, config, None)
+    }
+
+    /// Create an Etherscan client sending its requests through the given HTTP or SOCKS proxy.
+    ///
+    /// Without one, the proxy set with the `HTTP_PROXY`, `HTTPS_PROXY` or `ALL_PROXY` environment
+    /// variables is used, if any.
+    pub(crate) fn proxied_client(
+        &self,
+        chain: Chain,
+        verifier_url: Option<&str>,
+        etherscan_key: Option<&str>,
+        config: &Config,
+        proxy: Option<&Url>,
     ) -> Result<Client> {
         let etherscan_config = config.get_etherscan_config_with_chain(Some(chain))?;
 
@@ -284,6 +300,10 @@ impl EtherscanVerificationProvider {
             builder.chain(chain)?
         };
 
+        if proxy.is_some() {
+            builder = builder.with_client(proxied_http_client(proxy)?);
+        }
+
         builder
             .with_api_key(etherscan_key.unwrap_or_default())
             .build()
@@ -450,6 +470,16 @@ async fn ensure_solc_build_metadata(version: Version) -> Result<Version> {
     }
 }
 
+/// Creates an HTTP client sending its requests through the given HTTP or SOCKS proxy, or through
+/// the one set with the `HTTP_PROXY`, `HTTPS_PROXY` or `ALL_PROXY` environment variables.
+pub(crate) fn proxied_http_client(proxy: Option<&Url>) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy.clone())?);
+    }
+    builder.build().wrap_err("Failed to create the proxied HTTP client")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
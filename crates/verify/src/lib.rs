@@ -19,8 +19,13 @@ pub mod verify;
 pub use verify::{VerifierArgs, VerifyArgs, VerifyCheckArgs};
 
 mod types;
+pub use types::VerificationType;
 
 mod utils;
+pub use utils::{
+    BytecodeType, ConstructorArgsSource, CreationCodeSource, JsonResult, JsonResultDiff,
+    MatchVerdict,
+};
 
 #[macro_use]
 extern crate tracing;
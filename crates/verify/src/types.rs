@@ -1,10 +1,20 @@
+use alloy_primitives::{Address, Selector, B256, U256};
 use eyre::Result;
+use foundry_compilers::info::ContractInfo;
+use foundry_config::Chain;
+use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::{fmt, str::FromStr};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 /// Enum to represent the type of verification: `full` or `partial`.
 /// Ref: <https://docs.sourcify.dev/docs/full-vs-partial-match/>
-#[derive(Debug, Clone, clap::ValueEnum, Default, PartialEq, Eq, Serialize, Deserialize, Copy)]
+#[derive(
+    Debug, Clone, clap::ValueEnum, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Copy,
+)]
 pub enum VerificationType {
     #[default]
     #[serde(rename = "full")]
@@ -42,3 +52,199 @@ impl fmt::Display for VerificationType {
         }
     }
 }
+
+/// When to color the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color the output if stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// The format of the `--diff` output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffFormat {
+    /// The offset of the first difference and the source location it maps to.
+    #[default]
+    Text,
+    /// Each divergent run of bytes as JSON, with its offset and the local and on-chain bytes.
+    Binary,
+}
+
+impl ColorChoice {
+    /// Sets the global color output condition of [`yansi`].
+    pub fn apply(self) {
+        match self {
+            Self::Auto => {
+                if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                    yansi::disable();
+                }
+            }
+            Self::Always => yansi::enable(),
+            Self::Never => yansi::disable(),
+        }
+    }
+}
+
+/// Substitutes the constructor argument at `index` with `address`, in the form `<index>:<address>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkArg {
+    pub index: usize,
+    pub address: Address,
+}
+
+impl FromStr for LinkArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, address) =
+            s.split_once(':').ok_or_else(|| format!("Expected `<index>:<address>`, found: {s}"))?;
+        let index = index.parse().map_err(|e| format!("Invalid argument index `{index}`: {e}"))?;
+        let address = address.parse().map_err(|e| format!("Invalid address `{address}`: {e}"))?;
+        Ok(Self { index, address })
+    }
+}
+
+/// The value an immutable is expected to hold, in the form `<ref-id>=<value>`.
+///
+/// The reference id is the AST id the artifact's `immutableReferences` are keyed by. The value is
+/// a decimal or hex number, e.g. an address, and is compared as a left-padded 32 byte word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedImmutable {
+    pub id: String,
+    pub value: B256,
+}
+
+impl FromStr for ExpectedImmutable {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, value) =
+            s.split_once('=').ok_or_else(|| format!("Expected `<ref-id>=<value>`, found: {s}"))?;
+        let value =
+            value.parse::<U256>().map_err(|e| format!("Invalid immutable value `{value}`: {e}"))?;
+        Ok(Self { id: id.to_string(), value: value.into() })
+    }
+}
+
+/// A byte range of the runtime code to zero out before comparison, in the form `<start>:<len>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaskRange {
+    pub start: usize,
+    pub len: usize,
+}
+
+impl FromStr for MaskRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, len) =
+            s.split_once(':').ok_or_else(|| format!("Expected `<start>:<len>`, found: {s}"))?;
+        let start = start.parse().map_err(|e| format!("Invalid range start `{start}`: {e}"))?;
+        let len = len.parse().map_err(|e| format!("Invalid range length `{len}`: {e}"))?;
+        Ok(Self { start, len })
+    }
+}
+
+impl fmt::Display for MaskRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.start, self.len)
+    }
+}
+
+/// The contract a diamond facet is verified against, in the form `<selector>=<contract>`.
+///
+/// A facet is matched by any of the selectors it serves.
+#[derive(Clone, Debug)]
+pub struct FacetMapping {
+    pub selector: Selector,
+    pub contract: ContractInfo,
+}
+
+impl FromStr for FacetMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, contract) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected `<selector>=<contract>`, found: {s}"))?;
+        let selector =
+            selector.parse().map_err(|e| format!("Invalid selector `{selector}`: {e}"))?;
+        let contract =
+            contract.parse().map_err(|e| format!("Invalid contract `{contract}`: {e}"))?;
+        Ok(Self { selector, contract })
+    }
+}
+
+/// Selects one of the artifacts built for the contract, in the form `version=<version>` or
+/// `path=<path>`.
+///
+/// A contract has several artifacts when it's compiled with several compiler versions. The path
+/// matches the end of the artifact's path, e.g. `Counter.0.8.20.json`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArtifactSelector {
+    Version(Version),
+    Path(PathBuf),
+}
+
+impl ArtifactSelector {
+    /// Returns whether the artifact at `path`, built with the given compiler version, is the
+    /// selected one.
+    pub fn matches(&self, path: &Path, version: &Version) -> bool {
+        match self {
+            Self::Version(selected) => {
+                Version::new(version.major, version.minor, version.patch) == *selected
+            }
+            Self::Path(selected) => path.ends_with(selected),
+        }
+    }
+}
+
+impl FromStr for ArtifactSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected `version=<version>` or `path=<path>`, found: {s}"))?;
+        match kind {
+            "version" => {
+                let version = value.trim_start_matches('v');
+                let version =
+                    version.parse().map_err(|e| format!("Invalid version `{value}`: {e}"))?;
+                Ok(Self::Version(version))
+            }
+            "path" => Ok(Self::Path(PathBuf::from(value))),
+            _ => Err(format!("Unknown artifact selector `{kind}`, expected `version` or `path`")),
+        }
+    }
+}
+
+impl fmt::Display for ArtifactSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version(version) => write!(f, "version={version}"),
+            Self::Path(path) => write!(f, "path={}", path.display()),
+        }
+    }
+}
+
+/// A verifier URL to use for a specific chain, in the form `<chain>=<url>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainVerifierUrl {
+    pub chain: Chain,
+    pub url: String,
+}
+
+impl FromStr for ChainVerifierUrl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (chain, url) =
+            s.split_once('=').ok_or_else(|| format!("Expected `<chain>=<url>`, found: {s}"))?;
+        let chain = chain.parse().map_err(|e| format!("Invalid chain `{chain}`: {e}"))?;
+        Ok(Self { chain, url: url.to_string() })
+    }
+}
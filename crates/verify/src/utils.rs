@@ -1,30 +1,79 @@
-use crate::{bytecode::VerifyBytecodeArgs, types::VerificationType};
-use alloy_dyn_abi::DynSolValue;
-use alloy_primitives::{Address, Bytes, U256};
+use crate::{
+    bytecode::VerifyBytecodeArgs,
+    types::{ExpectedImmutable, LinkArg, MaskRange, VerificationType},
+};
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_primitives::{b256, hex, keccak256, Address, Bytes, Log, Selector, TxHash, B256, U256};
 use alloy_provider::Provider;
-use alloy_rpc_types::{Block, BlockId, Transaction};
+use alloy_rpc_types::{AnyTransactionReceipt, Block, BlockId, Transaction, TransactionRequest};
+use alloy_serde::WithOtherFields;
 use clap::ValueEnum;
-use eyre::{OptionExt, Result};
+use eyre::{OptionExt, Result, WrapErr};
 use foundry_block_explorers::{
     contract::{ContractCreationData, ContractMetadata, Metadata},
     errors::EtherscanError,
+    Client,
+};
+use foundry_cli::utils::{CommandUtils, Git};
+use foundry_common::{
+    abi::coerce_value, compile::ProjectCompiler, provider::RetryProvider, SYSTEM_TRANSACTION_TYPE,
+};
+use foundry_compilers::{
+    artifacts::{
+        output_selection::OutputSelection, sourcemap, BytecodeHash, BytecodeObject,
+        CompactBytecode, CompactContractBytecode, EvmVersion, Libraries, Offsets, SolcInput,
+    },
+    info::ContractInfo,
+    report::{self, Report, Reporter},
+    solc::Solc,
+};
+use foundry_config::{Chain, Config, NamedChain, SolcReq};
+use foundry_evm::{
+    constants::DEFAULT_CREATE2_DEPLOYER,
+    decode::RevertDecoder,
+    executors::{EvmError, TracingExecutor},
+    opts::EvmOpts,
+    utils::PcIcMap,
 };
-use foundry_common::{abi::encode_args, compile::ProjectCompiler, provider::RetryProvider};
-use foundry_compilers::artifacts::{BytecodeHash, CompactContractBytecode, EvmVersion};
-use foundry_config::Config;
-use foundry_evm::{constants::DEFAULT_CREATE2_DEPLOYER, executors::TracingExecutor, opts::EvmOpts};
 use reqwest::Url;
 use revm_primitives::{
     db::Database,
     env::{EnvWithHandlerCfg, HandlerCfg},
-    Bytecode, Env, SpecId,
+    BlobExcessGasAndPrice, Bytecode, Env, SpecId,
 };
 use semver::Version;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    future::Future,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use strum::IntoEnumIterator;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use yansi::Paint;
 
+/// Constructor arguments larger than this are considered implausible when extracted from the
+/// creation code, as they usually indicate a wrong contract match.
+pub const MAX_CONSTRUCTOR_ARGS_LEN: usize = 64 * 1024;
+
+/// The ids of the zkSync Era mainnet, Sepolia and Goerli testnets, which run the zkEVM.
+const ZKSYNC_CHAIN_IDS: [u64; 3] = [324, 300, 280];
+
+/// Returns whether the chain runs the zkEVM of zkSync Era, whose bytecode format differs from the
+/// EVM one.
+pub fn is_zksync_chain(chain: Chain) -> bool {
+    ZKSYNC_CHAIN_IDS.contains(&chain.id())
+}
+
 /// Enum to represent the type of bytecode being verified
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, ValueEnum)]
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum,
+)]
 pub enum BytecodeType {
     #[serde(rename = "creation")]
     Creation,
@@ -44,63 +93,757 @@ impl BytecodeType {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Resolves the bytecode type to ignore from `--ignore` and the bytecode types selected with
+/// `--only`, failing if they contradict each other.
+pub fn resolve_ignored_bytecode(
+    ignore: Option<BytecodeType>,
+    only: &[BytecodeType],
+) -> Result<Option<BytecodeType>> {
+    if only.is_empty() {
+        return Ok(ignore)
+    }
+    if let Some(ignore) = ignore.filter(|ignore| only.contains(ignore)) {
+        eyre::bail!(
+            "--only contradicts --ignore, both select the {} bytecode",
+            format!("{ignore:?}").to_lowercase()
+        )
+    }
+    let ignore_creation = !only.contains(&BytecodeType::Creation);
+    let ignore_runtime = !only.contains(&BytecodeType::Runtime);
+    Ok(match (ignore_creation, ignore_runtime) {
+        (true, _) => Some(BytecodeType::Creation),
+        (_, true) => Some(BytecodeType::Runtime),
+        _ => ignore,
+    })
+}
+
+/// Enum to represent where the constructor arguments used for verification came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstructorArgsSource {
+    /// Provided via `--constructor-args`.
+    #[serde(rename = "provided_decoded")]
+    ProvidedDecoded,
+    /// Provided via `--encoded-constructor-args`.
+    #[serde(rename = "provided_encoded")]
+    ProvidedEncoded,
+    /// Read from the file passed via `--constructor-args-path`.
+    #[serde(rename = "file")]
+    File,
+    /// Retrieved from the block explorer.
+    #[serde(rename = "etherscan")]
+    Etherscan,
+    /// Extracted from the tail of the on-chain creation code.
+    #[serde(rename = "extracted_from_bytecode")]
+    ExtractedFromBytecode,
+    /// Decoded from an event emitted by the creation tx, with `--args-from-event`.
+    #[serde(rename = "event")]
+    Event,
+}
+
+impl fmt::Display for ConstructorArgsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProvidedDecoded => write!(f, "--constructor-args"),
+            Self::ProvidedEncoded => write!(f, "--encoded-constructor-args"),
+            Self::File => write!(f, "--constructor-args-path"),
+            Self::Etherscan => write!(f, "the block explorer"),
+            Self::ExtractedFromBytecode => write!(f, "the on-chain creation code"),
+            Self::Event => write!(f, "--args-from-event"),
+        }
+    }
+}
+
+/// Enum to represent where the on-chain creation code came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CreationCodeSource {
+    /// Extracted from the input of the creation transaction.
+    #[serde(rename = "creation_tx")]
+    CreationTx,
+    /// The creation bytecode stored by the block explorer.
+    #[serde(rename = "explorer")]
+    Explorer,
+    /// Found in the traces of the creation transaction, in a frame nested in its call tree.
+    #[serde(rename = "creation_tx_trace")]
+    CreationTrace,
+}
+
+/// The result of verifying one bytecode type of a contract.
+///
+/// Fields are serialized in their declaration order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct JsonResult {
+    pub address: Address,
     pub bytecode_type: BytecodeType,
     pub match_type: Option<VerificationType>,
+    /// Whether all the verified bytecode types fully matched.
+    ///
+    /// With `--json-lines` each result is printed before the others are known, so it only
+    /// reflects the result itself.
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<MatchVerdict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meets_min_match: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constructor_args_source: Option<ConstructorArgsSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etherscan_contract_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoded_constructor_args: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_code_source: Option<CreationCodeSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alignment_offset: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_solc_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_solc_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_metadata_digest: Option<MetadataDigest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_metadata_digest: Option<MetadataDigest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_version: Option<EvmVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimizer: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimizer_runs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_tx_hash: Option<TxHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_code_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_code_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_code_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_onchain_code_hash: Option<B256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deploy_gas_used: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deploy_success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beacon_address: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_admin: Option<Address>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub immutables: Vec<ImmutableCheck>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub masked_ranges: Vec<MaskRange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub experimental_pragmas: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub library_address_masked: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub libraries: Vec<LibraryCheck>,
+    /// The divergent runs of bytes, with `--diff --diff-format binary`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diff: Vec<DiffRun>,
+}
+
+impl JsonResult {
+    pub fn new(
+        address: Address,
+        bytecode_type: BytecodeType,
+        verdict: Option<MatchVerdict>,
+        message: Option<String>,
+        details: &VerificationDetails,
+    ) -> Self {
+        let match_type = verdict.and_then(|verdict| verdict.match_type);
+        Self {
+            address,
+            bytecode_type,
+            match_type,
+            verified: match_type == Some(VerificationType::Full) &&
+                details.immutables.iter().all(|check| check.matches) &&
+                details.libraries.iter().all(|check| check.matches),
+            verdict,
+            meets_min_match: None,
+            message,
+            constructor_args_source: details.constructor_args_source,
+            etherscan_contract_name: details.etherscan_contract_name.clone(),
+            encoded_constructor_args: details.encoded_constructor_args.clone(),
+            creation_code_source: details.creation_code_source,
+            contract_path: details.contract_path.clone(),
+            alignment_offset: details.alignment_offset,
+            onchain_solc_version: details.onchain_solc_version.clone(),
+            local_solc_version: details.local_solc_version.clone(),
+            onchain_metadata_digest: details.onchain_metadata_digest.clone(),
+            local_metadata_digest: details.local_metadata_digest.clone(),
+            evm_version: details.evm_version,
+            optimizer: details.optimizer,
+            optimizer_runs: details.optimizer_runs,
+            creation_tx_hash: details.creation_tx_hash,
+            onchain_code_hash: details
+                .onchain_code_hash
+                .filter(|_| bytecode_type == BytecodeType::Runtime),
+            local_code_hash: details
+                .local_code_hash
+                .filter(|_| bytecode_type == BytecodeType::Runtime),
+            registry_code_hash: details
+                .registry_code_hash
+                .filter(|_| bytecode_type == BytecodeType::Runtime),
+            raw_onchain_code_hash: details
+                .raw_onchain_code_hash
+                .filter(|_| bytecode_type == BytecodeType::Runtime),
+            deploy_gas_used: details.deploy_gas_used,
+            deploy_success: details.deploy_success,
+            proxy_address: details.proxy_address,
+            beacon_address: details.beacon_address,
+            proxy_admin: details.proxy_admin,
+            immutables: details.immutables.clone(),
+            masked_ranges: if bytecode_type == BytecodeType::Runtime {
+                details.masked_ranges.clone()
+            } else {
+                Vec::new()
+            },
+            experimental_pragmas: details.experimental_pragmas.clone(),
+            library_address_masked: bytecode_type == BytecodeType::Runtime &&
+                details.library_address_masked,
+            libraries: if bytecode_type == BytecodeType::Runtime {
+                details.libraries.clone()
+            } else {
+                Vec::new()
+            },
+            diff: Vec::new(),
+        }
+    }
+
+    /// Returns whether all of the `--expect-immutable` checks passed.
+    pub fn immutables_match(&self) -> bool {
+        self.immutables.iter().all(|check| check.matches)
+    }
+
+    /// Returns whether the on-chain code calls the libraries it was linked to locally.
+    pub fn libraries_match(&self) -> bool {
+        self.libraries.iter().all(|check| check.matches)
+    }
+}
+
+impl JsonResult {
+    /// Compares the result against the expected one, returning the fields which differ.
+    ///
+    /// Both results are compared in their serialized form, so nested fields are reported by their
+    /// path, e.g. `verdict.similarity`, and omitted fields are compared as `null`.
+    pub fn diff(&self, expected: &Self) -> serde_json::Result<Vec<JsonResultDiff>> {
+        let mut diffs = vec![];
+        diff_json_values(
+            String::new(),
+            serde_json::to_value(expected)?,
+            serde_json::to_value(self)?,
+            &mut diffs,
+        );
+        Ok(diffs)
+    }
+}
+
+/// A field which differs between an expected and an actual [JsonResult].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct JsonResultDiff {
+    /// The path of the field, e.g. `match_type` or `verdict.similarity`.
+    pub field: String,
+    pub expected: serde_json::Value,
+    pub actual: serde_json::Value,
+}
+
+fn diff_json_values(
+    field: String,
+    expected: serde_json::Value,
+    actual: serde_json::Value,
+    diffs: &mut Vec<JsonResultDiff>,
+) {
+    match (expected, actual) {
+        (serde_json::Value::Object(mut expected), serde_json::Value::Object(mut actual)) => {
+            let mut keys = expected.keys().chain(actual.keys()).cloned().collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let expected = expected.remove(&key).unwrap_or_default();
+                let actual = actual.remove(&key).unwrap_or_default();
+                let field = if field.is_empty() { key } else { format!("{field}.{key}") };
+                diff_json_values(field, expected, actual, diffs);
+            }
+        }
+        (expected, actual) if expected != actual => {
+            diffs.push(JsonResultDiff { field, expected, actual })
+        }
+        _ => {}
+    }
+}
+
+/// Details of a verification run which are attached to each of its [JsonResult]s.
+#[derive(Clone, Debug, Default)]
+pub struct VerificationDetails {
+    /// Where the constructor arguments were resolved from.
+    pub constructor_args_source: Option<ConstructorArgsSource>,
+    /// The contract name reported by the block explorer, if it differs from the expected one.
+    pub etherscan_contract_name: Option<String>,
+    /// The final encoded constructor arguments, if they were altered by `--link-arg`.
+    pub encoded_constructor_args: Option<Bytes>,
+    /// Where the on-chain creation code came from.
+    pub creation_code_source: Option<CreationCodeSource>,
+    /// The source path and name of the local artifact, e.g. `src/Token.sol:Token`.
+    pub contract_path: Option<String>,
+    /// The offset by which the creation code was aligned with `--align`.
+    pub alignment_offset: Option<i64>,
+    /// The solc version embedded in the metadata of the on-chain runtime code.
+    pub onchain_solc_version: Option<String>,
+    /// The compiler version of the local artifact.
+    pub local_solc_version: Option<String>,
+    /// The metadata file digest embedded in the on-chain runtime code.
+    pub onchain_metadata_digest: Option<MetadataDigest>,
+    /// The metadata file digest embedded in the local runtime code.
+    pub local_metadata_digest: Option<MetadataDigest>,
+    /// The EVM version the contract was built with.
+    pub evm_version: Option<EvmVersion>,
+    /// Whether the contract was built with the optimizer.
+    pub optimizer: Option<bool>,
+    /// The optimizer runs the contract was built with, if the optimizer was enabled.
+    pub optimizer_runs: Option<usize>,
+    /// The hash of the tx which created the contract.
+    pub creation_tx_hash: Option<TxHash>,
+    /// The keccak256 hash of the on-chain runtime code.
+    pub onchain_code_hash: Option<B256>,
+    /// The keccak256 hash of the local runtime code, as deployed on the fork if simulated.
+    pub local_code_hash: Option<B256>,
+    /// The runtime code hash published by the `--registry`.
+    pub registry_code_hash: Option<B256>,
+    /// The keccak256 hash of the on-chain runtime code compared against the `--registry`'s, which
+    /// unlike `onchain_code_hash` isn't normalized by `--hash-only`.
+    pub raw_onchain_code_hash: Option<B256>,
+    /// The gas used by the deployment simulated on the fork.
+    pub deploy_gas_used: Option<u64>,
+    /// Whether the deployment simulated on the fork halted successfully.
+    pub deploy_success: Option<bool>,
+    /// The address of the proxy, if its implementation was verified.
+    pub proxy_address: Option<Address>,
+    /// The address of the beacon the implementation was resolved from, for beacon proxies.
+    pub beacon_address: Option<Address>,
+    /// The admin of the proxy, which can upgrade it, if its admin slot is set.
+    pub proxy_admin: Option<Address>,
+    /// The results of the `--expect-immutable` checks against the on-chain runtime code.
+    pub immutables: Vec<ImmutableCheck>,
+    /// The byte ranges of the runtime code masked with `--mask-range`.
+    pub masked_ranges: Vec<MaskRange>,
+    /// The features enabled with `pragma experimental` in the verified source, e.g.
+    /// `ABIEncoderV2`.
+    pub experimental_pragmas: Vec<String>,
+    /// Whether the address of the library call guard was masked in the runtime codes.
+    pub library_address_masked: bool,
+    /// The offsets of the addresses of each library called by the runtime code.
+    pub library_references: BTreeMap<String, Vec<usize>>,
+    /// The addresses the libraries were linked to locally.
+    pub linked_libraries: BTreeMap<String, Address>,
+    /// The results of checking the library addresses of the on-chain runtime code against the
+    /// linked ones.
+    pub libraries: Vec<LibraryCheck>,
+}
+
+/// The verdict of comparing a local bytecode against the on-chain one, decomposed into its parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatchVerdict {
+    /// The overall classification of the match.
+    #[serde(skip)]
+    pub match_type: Option<VerificationType>,
+    /// Whether the bytecodes match once the metadata and constructor args are stripped.
+    pub body_match: bool,
+    /// Whether the metadata matches, `None` if neither bytecode has any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_match: Option<bool>,
+    /// Whether the constructor args match, `None` if they weren't compared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constructor_args_match: Option<bool>,
+    /// The ratio of identical bytes between the stripped bytecodes, from 0.0 to 1.0.
+    pub similarity: f64,
+    /// The number of trailing zero bytes ignored with `--ignore-trailing-zeros`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trailing_zeros_ignored: Option<usize>,
+}
+
+impl MatchVerdict {
+    /// Returns a human readable breakdown of the verdict, e.g. "opcodes match, metadata differs".
+    pub fn describe(&self) -> String {
+        let verdict = |matched: bool| if matched { "match" } else { "differ" };
+        let mut parts = vec![format!("opcodes {}", verdict(self.body_match))];
+        if let Some(matched) = self.metadata_match {
+            parts.push(format!("metadata {}", if matched { "matches" } else { "differs" }));
+        }
+        if let Some(matched) = self.constructor_args_match {
+            parts.push(format!("constructor args {}", verdict(matched)));
+        }
+        if let Some(zeros) = self.trailing_zeros_ignored {
+            parts.push(format!("{zeros} trailing zero bytes ignored"));
+        }
+        parts.join(", ")
+    }
 }
 
 pub fn match_bytecodes(
-    local_bytecode: &[u8],
-    bytecode: &[u8],
+    mut local_bytecode: &[u8],
+    mut bytecode: &[u8],
     constructor_args: &[u8],
     is_runtime: bool,
     bytecode_hash: BytecodeHash,
-) -> Option<VerificationType> {
+    ignore_trailing_zeros: bool,
+) -> MatchVerdict {
+    // Strip the zero padding by which one side exceeds the other, so that only non-zero trailing
+    // bytes count as a difference.
+    let mut trailing_zeros_ignored = None;
+    if ignore_trailing_zeros && is_runtime {
+        let local_zeros = trailing_zeros(local_bytecode);
+        let zeros = trailing_zeros(bytecode);
+        let common = local_zeros.min(zeros);
+        local_bytecode = &local_bytecode[..local_bytecode.len() - (local_zeros - common)];
+        bytecode = &bytecode[..bytecode.len() - (zeros - common)];
+        trailing_zeros_ignored = Some(local_zeros.max(zeros) - common).filter(|n| *n > 0);
+    }
+
+    let mut verdict =
+        compare_bytecode_parts(local_bytecode, bytecode, constructor_args, is_runtime);
+    verdict.trailing_zeros_ignored = trailing_zeros_ignored;
+
     // 1. Try full match
     if local_bytecode == bytecode {
         // If the bytecode_hash = 'none' in Config. Then it's always a partial match according to
         // sourcify definitions. Ref: https://docs.sourcify.dev/docs/full-vs-partial-match/.
-        if bytecode_hash == BytecodeHash::None {
-            return Some(VerificationType::Partial);
+        // Ignored padding also means the bytecodes are not strictly identical.
+        if bytecode_hash == BytecodeHash::None || trailing_zeros_ignored.is_some() {
+            verdict.match_type = Some(VerificationType::Partial);
+        } else {
+            verdict.match_type = Some(VerificationType::Full);
         }
+    } else if verdict.body_match {
+        verdict.match_type = Some(VerificationType::Partial);
+    }
+
+    verdict
+}
 
-        Some(VerificationType::Full)
+/// Returns the keccak256 hash of the code for `--hash-only`, without its metadata if
+/// `strip_metadata` is set.
+pub fn normalized_code_hash(code: &[u8], strip_metadata: bool) -> B256 {
+    if strip_metadata {
+        keccak256(extract_metadata_hash(code))
     } else {
-        is_partial_match(local_bytecode, bytecode, constructor_args, is_runtime)
-            .then_some(VerificationType::Partial)
+        keccak256(code)
     }
 }
 
-pub fn build_project(
-    args: &VerifyBytecodeArgs,
-    config: &Config,
-) -> Result<CompactContractBytecode> {
+/// Returns the verdict of the `--hash-only` comparison of the code hashes, `None` if they differ.
+///
+/// Like identical bytecodes, identical hashes are only a partial match without metadata.
+pub fn hash_verdict(
+    local_code_hash: B256,
+    onchain_code_hash: B256,
+    metadata_stripped: bool,
+    bytecode_hash: BytecodeHash,
+) -> Option<MatchVerdict> {
+    if local_code_hash != onchain_code_hash {
+        return None
+    }
+    let match_type = if metadata_stripped || bytecode_hash == BytecodeHash::None {
+        VerificationType::Partial
+    } else {
+        VerificationType::Full
+    };
+    Some(MatchVerdict {
+        match_type: Some(match_type),
+        body_match: true,
+        similarity: 1.0,
+        ..Default::default()
+    })
+}
+
+/// Returns the number of trailing zero bytes of the bytecode.
+fn trailing_zeros(bytecode: &[u8]) -> usize {
+    bytecode.iter().rev().take_while(|byte| **byte == 0).count()
+}
+
+/// The local artifact of the contract being verified.
+#[derive(Debug)]
+pub struct LocalArtifact {
+    pub bytecode: CompactContractBytecode,
+    /// The source file the artifact was compiled from, if known.
+    pub source_path: Option<PathBuf>,
+    /// The source files of the compilation by their ids, used to resolve source map locations.
+    pub sources: BTreeMap<u32, PathBuf>,
+    /// The version of the compiler the artifact was built with, if known.
+    pub compiler_version: Option<Version>,
+}
+
+/// Compiles the project and returns the artifact of the contract.
+pub fn build_project(args: &VerifyBytecodeArgs, config: &Config) -> Result<LocalArtifact> {
     let project = config.project()?;
-    let compiler = ProjectCompiler::new();
 
-    let mut output = compiler.compile(&project)?;
+    let output = if !args.json {
+        println!("Compiling project to obtain the artifact of {}", args.contract().name);
+        ProjectCompiler::new().compile(&project)?
+    } else if args.json_progress {
+        // Keep stdout clean for the json output.
+        let output = report::with_scoped(&Report::new(StderrReporter), || project.compile())?;
+        if output.has_compiler_errors() {
+            eyre::bail!("{output}")
+        }
+        output
+    } else {
+        ProjectCompiler::new().quiet(true).compile(&project)?
+    };
+
+    let candidates = output
+        .artifact_ids()
+        .filter(|(id, _)| {
+            id.name == args.contract().name &&
+                args.contract().path.as_ref().map_or(true, |path| id.source.ends_with(path))
+        })
+        .collect::<Vec<_>>();
+    let available = candidates
+        .iter()
+        .map(|(id, _)| format!("{} (solc {})", id.path.display(), id.version))
+        .collect::<Vec<_>>();
+    let (artifact_id, artifact) = match &args.artifact_selector {
+        Some(selector) => candidates
+            .into_iter()
+            .find(|(id, _)| selector.matches(&id.path, &id.version))
+            .ok_or_else(|| {
+                eyre::eyre!(
+                    "No artifact of {} matches --artifact-selector {selector}, found: {}",
+                    args.contract().name,
+                    available.join(", ")
+                )
+            })?,
+        None => {
+            if candidates.len() > 1 && !args.json {
+                println!(
+                    "{}",
+                    format!(
+                        "Found {} artifacts of {}, using {}; pick another one with \
+                         --artifact-selector: {}",
+                        candidates.len(),
+                        args.contract().name,
+                        available[0],
+                        available[1..].join(", ")
+                    )
+                    .yellow()
+                );
+            }
+            candidates
+                .into_iter()
+                .next()
+                .ok_or_eyre("Build Error: Contract artifact not found locally")?
+        }
+    };
+    let sources = output
+        .output()
+        .sources
+        .sources_with_version()
+        .filter(|(_, _, version)| **version == artifact_id.version)
+        .map(|(path, source_file, _)| (source_file.id, path.clone()))
+        .collect();
+
+    if !args.json {
+        println!("Artifact of {} ready", args.contract().name);
+    }
+
+    Ok(LocalArtifact {
+        bytecode: artifact.clone().into_contract_bytecode(),
+        compiler_version: Some(artifact_id.version),
+        source_path: Some(artifact_id.source),
+        sources,
+    })
+}
+
+/// Checks that the artifact holds the bytecode outputs needed for the verification, which are
+/// missing if the `output_selection` of the project leaves them out.
+///
+/// Interfaces and abstract contracts have no bytecode either.
+pub fn check_artifact_outputs(
+    artifact: &CompactContractBytecode,
+    contract_name: &str,
+    creation: bool,
+    runtime: bool,
+) -> Result<()> {
+    let is_missing = |bytecode: Option<&CompactBytecode>| {
+        bytecode.map_or(true, |bytecode| match &bytecode.object {
+            BytecodeObject::Bytecode(bytes) => bytes.is_empty(),
+            BytecodeObject::Unlinked(code) => code.is_empty(),
+        })
+    };
+    let deployed_bytecode =
+        artifact.deployed_bytecode.as_ref().and_then(|deployed| deployed.bytecode.as_ref());
+    let missing = [
+        (creation && is_missing(artifact.bytecode.as_ref())).then_some("evm.bytecode"),
+        (runtime && is_missing(deployed_bytecode)).then_some("evm.deployedBytecode"),
+    ];
+    let missing = missing.into_iter().flatten().collect::<Vec<_>>();
+    if !missing.is_empty() {
+        eyre::bail!(
+            "The artifact of {contract_name} lacks {}, make sure the output_selection of the \
+             project includes the missing outputs and that {contract_name} is not abstract",
+            missing.join(" and ")
+        )
+    }
+    Ok(())
+}
+
+/// A combination of compiler settings tried by `--try-compilers` or `--compare-optimizer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompilerCandidate {
+    pub version: Version,
+    pub optimizer: bool,
+    pub optimizer_runs: usize,
+    pub evm_version: EvmVersion,
+}
+
+impl fmt::Display for CompilerCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.optimizer {
+            write!(f, "solc {}, {} runs, {}", self.version, self.optimizer_runs, self.evm_version)
+        } else {
+            write!(f, "solc {}, optimizer disabled, {}", self.version, self.evm_version)
+        }
+    }
+}
+
+/// Returns each combination of the given versions and runs, falling back to `default_runs` if no
+/// runs are given.
+pub fn compiler_candidates(
+    versions: &[Version],
+    runs: &[usize],
+    default_runs: usize,
+    optimizer: bool,
+    evm_version: EvmVersion,
+) -> Vec<CompilerCandidate> {
+    let runs = if runs.is_empty() { std::slice::from_ref(&default_runs) } else { runs };
+    versions
+        .iter()
+        .flat_map(|version| {
+            runs.iter().map(|optimizer_runs| CompilerCandidate {
+                version: version.clone(),
+                optimizer,
+                optimizer_runs: *optimizer_runs,
+                evm_version,
+            })
+        })
+        .collect()
+}
+
+/// Returns the settings compared by `--compare-optimizer`: the given ones with the optimizer
+/// enabled, and with it disabled.
+pub fn optimizer_candidates(
+    version: Version,
+    optimizer_runs: usize,
+    evm_version: EvmVersion,
+) -> Vec<CompilerCandidate> {
+    [true, false]
+        .into_iter()
+        .map(|optimizer| CompilerCandidate {
+            version: version.clone(),
+            optimizer,
+            optimizer_runs,
+            evm_version,
+        })
+        .collect()
+}
+
+/// Parses the solc version reported by a block explorer, e.g. `v0.8.20+commit.a1b79de6`.
+pub fn parse_explorer_compiler_version(version: &str) -> Result<Version> {
+    if version.starts_with("vyper:") {
+        eyre::bail!("Vyper contracts are not supported")
+    }
+    let stripped = version.split('+').next().unwrap_or_default().trim_start_matches('v');
+    Version::parse(stripped)
+        .wrap_err_with(|| format!("Invalid compiler version from the block explorer: {version}"))
+}
+
+/// Builds the contract with the candidate's settings into a temporary directory, and compares its
+/// creation code against the on-chain one.
+pub fn build_candidate(
+    args: &VerifyBytecodeArgs,
+    mut config: Config,
+    candidate: &CompilerCandidate,
+    creation_code: &[u8],
+    constructor_args: Bytes,
+) -> Result<(MatchVerdict, LocalArtifact)> {
+    // Concurrent builds must not share the artifacts and the cache.
+    let dir = tempfile::tempdir()?;
+    config.out = dir.path().join("out");
+    config.cache_path = dir.path().join("cache");
+    config.solc = Some(SolcReq::Version(candidate.version.clone()));
+    config.optimizer = candidate.optimizer;
+    config.optimizer_runs = candidate.optimizer_runs;
+    config.evm_version = candidate.evm_version;
+
+    let artifact = build_project(args, &config)?;
+    let local_bytecode = linked_bytes(artifact.bytecode.bytecode.as_ref())?;
+
+    let constructor_args = if creation_code.ends_with(&constructor_args) {
+        constructor_args
+    } else {
+        extract_constructor_args(creation_code, local_bytecode.len()).unwrap_or_default()
+    };
+    let mut local_bytecode = local_bytecode.to_vec();
+    local_bytecode.extend_from_slice(&constructor_args);
+
+    let verdict = match_bytecodes(
+        &local_bytecode,
+        creation_code,
+        &constructor_args,
+        false,
+        config.bytecode_hash,
+        args.ignore_trailing_zeros,
+    );
+    Ok((verdict, artifact))
+}
+
+/// A [Reporter] which emits compilation progress to stderr.
+#[derive(Debug)]
+pub struct StderrReporter;
+
+impl Reporter for StderrReporter {
+    fn on_compiler_spawn(&self, compiler_name: &str, version: &Version, dirty_files: &[PathBuf]) {
+        eprintln!(
+            "Compiling {} files with {} {}.{}.{}",
+            dirty_files.len(),
+            compiler_name,
+            version.major,
+            version.minor,
+            version.patch
+        );
+    }
+
+    fn on_compiler_success(&self, compiler_name: &str, version: &Version, duration: &Duration) {
+        eprintln!(
+            "{} {}.{}.{} finished in {duration:.2?}",
+            compiler_name, version.major, version.minor, version.patch
+        );
+    }
 
-    let artifact = output
-        .remove_contract(&args.contract)
-        .ok_or_eyre("Build Error: Contract artifact not found locally")?;
+    fn on_solc_installation_start(&self, version: &Version) {
+        eprintln!("Installing Solc version {version}");
+    }
 
-    Ok(artifact.into_contract_bytecode())
+    fn on_solc_installation_success(&self, version: &Version) {
+        eprintln!("Successfully installed Solc {version}");
+    }
 }
 
 pub fn build_using_cache(
     args: &VerifyBytecodeArgs,
     etherscan_settings: &Metadata,
     config: &Config,
-) -> Result<CompactContractBytecode> {
+) -> Result<LocalArtifact> {
     let project = config.project()?;
     let cache = project.read_cache_file()?;
     let cached_artifacts = cache.read_artifacts::<CompactContractBytecode>()?;
 
     for (key, value) in cached_artifacts {
-        let name = args.contract.name.to_owned() + ".sol";
+        let name = args.contract().name.to_owned() + ".sol";
         let version = etherscan_settings.compiler_version.to_owned();
         // Ignores vyper
         if version.starts_with("vyper:") {
@@ -128,311 +871,3734 @@ pub fn build_using_cache(
                     }
                 }
 
-                return Ok(artifact.artifact)
+                if args
+                    .artifact_selector
+                    .as_ref()
+                    .is_some_and(|selector| !selector.matches(&artifact.file, &artifact.version))
+                {
+                    continue;
+                }
+
+                return Ok(LocalArtifact {
+                    bytecode: artifact.artifact,
+                    source_path: Some(PathBuf::from(key)),
+                    sources: BTreeMap::new(),
+                    compiler_version: Some(artifact.version),
+                })
             }
         }
     }
 
-    eyre::bail!("couldn't find cached artifact for contract {}", args.contract.name)
+    eyre::bail!("couldn't find cached artifact for contract {}", args.contract().name)
+}
+
+/// Compiles the standard JSON input at the given path with the given solc version and returns the
+/// artifact of the contract, along with the bytecode hash setting of the input.
+pub fn compile_standard_json(
+    path: &Path,
+    version: &Version,
+    contract: &ContractInfo,
+) -> Result<(LocalArtifact, BytecodeHash)> {
+    let mut input: SolcInput = foundry_common::fs::read_json_file(path)
+        .wrap_err_with(|| format!("Failed to read standard JSON input {}", path.display()))?;
+    input.settings.output_selection = OutputSelection::common_output_selection(
+        ["abi", "evm.bytecode", "evm.deployedBytecode"].map(String::from),
+    );
+    let bytecode_hash = input
+        .settings
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.bytecode_hash)
+        .unwrap_or_default();
+
+    let solc = Solc::find_or_install(version)?;
+    let output = solc.compile_exact(&input)?;
+
+    let errors = output.errors.iter().filter(|error| error.severity.is_error()).collect::<Vec<_>>();
+    if !errors.is_empty() {
+        eyre::bail!(
+            "Compiler errors:\n{}",
+            errors.into_iter().map(|error| error.to_string()).collect::<String>()
+        );
+    }
+
+    let mut matching = output.contracts.iter().flat_map(|(file, contracts)| {
+        contracts
+            .get(&contract.name)
+            .filter(|_| contract.path.as_ref().map_or(true, |path| Path::new(file).ends_with(path)))
+            .map(|artifact| (Path::new(file).to_path_buf(), artifact))
+    });
+    let Some((source_path, artifact)) = matching.next() else {
+        eyre::bail!("Contract {} not found in the standard JSON output", contract.name)
+    };
+    if matching.next().is_some() {
+        eyre::bail!(
+            "Found several contracts named {} in the standard JSON output, specify it as `<path>:<contractname>`",
+            contract.name
+        )
+    }
+
+    let sources = output
+        .sources
+        .iter()
+        .map(|(file, source)| (source.id, Path::new(file).to_path_buf()))
+        .collect();
+
+    Ok((
+        LocalArtifact {
+            bytecode: artifact.clone().into(),
+            source_path: Some(source_path),
+            sources,
+            compiler_version: Some(version.clone()),
+        },
+        bytecode_hash,
+    ))
 }
 
+/// Prints the result of a comparison and records it in `json_results`.
+///
+/// `verdict` is `None` if the bytecodes weren't compared, e.g. because the creation code already
+/// did not match.
 pub fn print_result(
     args: &VerifyBytecodeArgs,
-    res: Option<VerificationType>,
+    verdict: Option<MatchVerdict>,
     bytecode_type: BytecodeType,
     json_results: &mut Vec<JsonResult>,
-    etherscan_config: &Metadata,
+    etherscan_config: Option<&Metadata>,
     config: &Config,
-) {
-    if let Some(res) = res {
-        if !args.json {
+    details: &VerificationDetails,
+) -> Result<()> {
+    let res = verdict.and_then(|verdict| verdict.match_type);
+    let meets_min_match = args
+        .min_match
+        .map(|min_match| verdict.is_some_and(|verdict| verdict.similarity >= min_match));
+
+    if !args.json {
+        if let Some(res) = res {
             println!(
                 "{} with status {}",
                 format!("{bytecode_type:?} code matched").green().bold(),
                 res.green().bold()
             );
+            if let Some(verdict) = verdict.filter(|_| res == VerificationType::Partial) {
+                println!("{}", verdict.describe().yellow());
+            }
         } else {
-            let json_res = JsonResult { bytecode_type, match_type: Some(res), message: None };
-            json_results.push(json_res);
-        }
-    } else if !args.json {
-        println!(
-            "{}",
-            format!(
-                "{bytecode_type:?} code did not match - this may be due to varying compiler settings"
-            )
-            .red()
-            .bold()
-        );
-        let mismatches = find_mismatch_in_settings(etherscan_config, config);
-        for mismatch in mismatches {
-            println!("{}", mismatch.red().bold());
+            println!(
+                "{}",
+                format!(
+                    "{bytecode_type:?} code did not match - this may be due to varying compiler settings"
+                )
+                .red()
+                .bold()
+            );
+            if let Some(verdict) = verdict {
+                println!("{}", verdict.describe().red());
+            }
+            if let Some(etherscan_config) = etherscan_config {
+                let mismatches = find_mismatch_in_settings(etherscan_config, config);
+                for mismatch in mismatches {
+                    println!("{}", mismatch.red().bold());
+                }
+            }
+            if let Some(local_solc_version) = &details.local_solc_version {
+                match &details.onchain_solc_version {
+                    Some(onchain_solc_version) if onchain_solc_version != local_solc_version => {
+                        println!(
+                            "{}",
+                            format!(
+                                "on-chain solc: {onchain_solc_version}, local solc: {local_solc_version}"
+                            )
+                            .red()
+                            .bold()
+                        );
+                    }
+                    Some(_) => {}
+                    None => println!(
+                        "{}",
+                        "The on-chain metadata does not contain a solc version".red().bold()
+                    ),
+                }
+            }
+            if let (Some(onchain_digest), Some(local_digest)) =
+                (&details.onchain_metadata_digest, &details.local_metadata_digest)
+            {
+                if onchain_digest.digest_type != local_digest.digest_type {
+                    println!(
+                        "{}",
+                        format!(
+                            "on-chain metadata digest: {}, local metadata digest: {}",
+                            onchain_digest.digest_type, local_digest.digest_type
+                        )
+                        .red()
+                        .bold()
+                    );
+                }
+            }
         }
-    } else {
-        let json_res = JsonResult {
-            bytecode_type,
-            match_type: res,
-            message: Some(format!(
-                "{bytecode_type:?} code did not match - this may be due to varying compiler settings"
-            )),
-        };
-        json_results.push(json_res);
     }
-}
 
-fn is_partial_match(
-    mut local_bytecode: &[u8],
-    mut bytecode: &[u8],
-    constructor_args: &[u8],
-    is_runtime: bool,
-) -> bool {
-    // 1. Check length of constructor args
-    if constructor_args.is_empty() || is_runtime {
-        // Assume metadata is at the end of the bytecode
-        return try_extract_and_compare_bytecode(local_bytecode, bytecode)
+    let message = if res.is_none() {
+        Some(format!(
+            "{bytecode_type:?} code did not match - this may be due to varying compiler settings"
+        ))
+    } else if meets_min_match == Some(false) {
+        let similarity = verdict.map_or(0.0, |verdict| verdict.similarity);
+        Some(format!(
+            "{bytecode_type:?} code similarity of {:.2}% is below the required {:.2}%",
+            similarity * 100.0,
+            args.min_match.unwrap_or_default() * 100.0
+        ))
+    } else {
+        None
+    };
+    if let Some(message) = message.as_ref().filter(|_| !args.json && res.is_some()) {
+        println!("{}", message.red().bold());
     }
 
-    // If not runtime, extract constructor args from the end of the bytecode
-    bytecode = &bytecode[..bytecode.len() - constructor_args.len()];
-    local_bytecode = &local_bytecode[..local_bytecode.len() - constructor_args.len()];
+    let mut json_res = JsonResult::new(args.address(), bytecode_type, verdict, message, details);
+    json_res.meets_min_match = meets_min_match;
+    if args.json_lines {
+        println!("{}", serde_json::to_string(&json_res)?);
+    }
+    json_results.push(json_res);
 
-    try_extract_and_compare_bytecode(local_bytecode, bytecode)
+    Ok(())
 }
 
-fn try_extract_and_compare_bytecode(mut local_bytecode: &[u8], mut bytecode: &[u8]) -> bool {
-    local_bytecode = extract_metadata_hash(local_bytecode);
-    bytecode = extract_metadata_hash(bytecode);
+/// Sets the `verified` flag of each result, which requires all of them to be full matches with the
+/// expected immutables and libraries.
+pub fn mark_verified(json_results: &mut [JsonResult]) {
+    let verified = !json_results.is_empty() &&
+        json_results.iter().all(|res| {
+            res.match_type == Some(VerificationType::Full) &&
+                res.immutables_match() &&
+                res.libraries_match()
+        });
+    for res in json_results {
+        res.verified = verified;
+    }
+}
 
-    // Now compare the local code and bytecode
-    local_bytecode == bytecode
+/// The aggregate counts of verification results printed with `--summary-json`.
+///
+/// The matches count the verified bytecodes, i.e. up to two per contract, while the errors count
+/// the contracts whose verification failed before producing any result.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationSummary {
+    /// The number of verified bytecodes, i.e. `full + partial + none`.
+    pub total: usize,
+    pub full: usize,
+    pub partial: usize,
+    pub none: usize,
+    /// The number of contracts whose verification failed with an error before producing any
+    /// result.
+    pub errored: usize,
 }
 
-/// @dev This assumes that the metadata is at the end of the bytecode
-fn extract_metadata_hash(bytecode: &[u8]) -> &[u8] {
-    // Get the last two bytes of the bytecode to find the length of CBOR metadata
-    let metadata_len = &bytecode[bytecode.len() - 2..];
-    let metadata_len = u16::from_be_bytes([metadata_len[0], metadata_len[1]]);
+impl VerificationSummary {
+    pub fn new(json_results: &[JsonResult]) -> Self {
+        let mut summary = Self::default();
+        summary.add_results(json_results);
+        summary
+    }
 
-    if metadata_len as usize <= bytecode.len() {
-        if ciborium::from_reader::<ciborium::Value, _>(
-            &bytecode[bytecode.len() - 2 - metadata_len as usize..bytecode.len() - 2],
-        )
-        .is_ok()
-        {
-            &bytecode[..bytecode.len() - 2 - metadata_len as usize]
-        } else {
-            bytecode
+    /// Counts the results of another verification.
+    pub fn add_results(&mut self, json_results: &[JsonResult]) {
+        self.total += json_results.len();
+        for res in json_results {
+            match res.match_type {
+                Some(VerificationType::Full) => self.full += 1,
+                Some(VerificationType::Partial) => self.partial += 1,
+                None => self.none += 1,
+            }
         }
-    } else {
-        bytecode
+    }
+
+    /// Counts a contract whose verification failed with an error before producing any result.
+    pub fn add_error(&mut self) {
+        self.errored += 1;
+    }
+
+    /// Adds the counts of another summary, e.g. of the facets of a diamond in a batch.
+    pub fn merge(&mut self, other: Self) {
+        self.total += other.total;
+        self.full += other.full;
+        self.partial += other.partial;
+        self.none += other.none;
+        self.errored += other.errored;
     }
 }
 
-fn find_mismatch_in_settings(
-    etherscan_settings: &Metadata,
-    local_settings: &Config,
-) -> Vec<String> {
-    let mut mismatches: Vec<String> = vec![];
-    if etherscan_settings.evm_version != local_settings.evm_version.to_string().to_lowercase() {
-        let str = format!(
-            "EVM version mismatch: local={}, onchain={}",
-            local_settings.evm_version, etherscan_settings.evm_version
-        );
-        mismatches.push(str);
+/// Returns why a contract created at `creation_block` is outside of the `--deployed-after` and
+/// `--deployed-before` window, if it is. Both bounds are exclusive.
+pub fn deployment_window_violation(
+    creation_block: u64,
+    deployed_after: Option<u64>,
+    deployed_before: Option<u64>,
+) -> Option<String> {
+    if let Some(after) = deployed_after.filter(|after| creation_block <= *after) {
+        return Some(format!("created at block {creation_block}, not after --deployed-after {after}"))
     }
-    let local_optimizer: u64 = if local_settings.optimizer { 1 } else { 0 };
-    if etherscan_settings.optimization_used != local_optimizer {
-        let str = format!(
-            "Optimizer mismatch: local={}, onchain={}",
-            local_settings.optimizer, etherscan_settings.optimization_used
-        );
-        mismatches.push(str);
+    if let Some(before) = deployed_before.filter(|before| creation_block >= *before) {
+        return Some(format!(
+            "created at block {creation_block}, not before --deployed-before {before}"
+        ))
     }
-    if etherscan_settings.runs != local_settings.optimizer_runs as u64 {
-        let str = format!(
-            "Optimizer runs mismatch: local={}, onchain={}",
-            local_settings.optimizer_runs, etherscan_settings.runs
-        );
-        mismatches.push(str);
+    None
+}
+
+/// Returns the single line verdict printed with `--quiet`, e.g. `FULL MATCH`, `PARTIAL (runtime)`
+/// or `NO MATCH`.
+pub fn quiet_verdict(json_results: &[JsonResult]) -> String {
+    if json_results.is_empty() || json_results.iter().any(|res| res.match_type.is_none()) {
+        return "NO MATCH".to_string()
+    }
+    let partial = json_results
+        .iter()
+        .filter(|res| res.match_type == Some(VerificationType::Partial))
+        .map(|res| format!("{:?}", res.bytecode_type).to_lowercase())
+        .collect::<Vec<_>>();
+    if partial.is_empty() {
+        "FULL MATCH".to_string()
+    } else {
+        format!("PARTIAL ({})", partial.join(", "))
     }
+}
 
-    mismatches
+/// The JUnit XML report written with `--junit`, with a test suite per verified contract and a
+/// test case per verified bytecode type.
+#[derive(Debug, Default)]
+pub struct JunitReport {
+    suites: Vec<JunitSuite>,
 }
 
-pub fn maybe_predeploy_contract(
-    creation_data: Result<ContractCreationData, EtherscanError>,
-) -> Result<(Option<ContractCreationData>, bool), eyre::ErrReport> {
-    let mut maybe_predeploy = false;
-    match creation_data {
-        Ok(creation_data) => Ok((Some(creation_data), maybe_predeploy)),
-        // Ref: https://explorer.mode.network/api?module=contract&action=getcontractcreation&contractaddresses=0xC0d3c0d3c0D3c0d3C0D3c0D3C0d3C0D3C0D30010
-        Err(EtherscanError::EmptyResult { status, message })
-            if status == "1" && message == "OK" =>
+#[derive(Debug)]
+struct JunitSuite {
+    name: String,
+    cases: Vec<JunitCase>,
+}
+
+#[derive(Debug)]
+struct JunitCase {
+    name: String,
+    /// The message and details of the failure, if the case failed.
+    failure: Option<(String, String)>,
+    /// Whether the failure is an error which prevented the verification.
+    error: bool,
+}
+
+impl JunitReport {
+    /// Adds the results of the verification of a contract.
+    ///
+    /// A bytecode type fails if it didn't match, is below `--min-match` or doesn't hold the
+    /// expected immutables or libraries.
+    pub fn add_results(&mut self, contract_name: &str, address: Address, results: &[JsonResult]) {
+        let cases = results
+            .iter()
+            .map(|res| {
+                let name = format!("{:?}", res.bytecode_type).to_lowercase();
+                let failure = if res.match_type.is_none() {
+                    Some(format!("{:?} code did not match", res.bytecode_type))
+                } else if res.meets_min_match == Some(false) {
+                    Some(format!("{:?} code is below the required similarity", res.bytecode_type))
+                } else if !res.immutables_match() {
+                    Some("Immutables do not hold the expected values".to_string())
+                } else if !res.libraries_match() {
+                    Some("Libraries are called at other addresses than the linked ones".to_string())
+                } else {
+                    None
+                };
+                let failure = failure.map(|message| {
+                    let details =
+                        [res.verdict.map(|verdict| verdict.describe()), res.message.clone()];
+                    (message, details.into_iter().flatten().collect::<Vec<_>>().join("\n"))
+                });
+                JunitCase { name, failure, error: false }
+            })
+            .collect();
+        self.suites.push(JunitSuite { name: format!("{contract_name} ({address})"), cases });
+    }
+
+    /// Adds a contract whose verification failed with an error.
+    pub fn add_error(&mut self, contract_name: &str, address: Address, err: &eyre::Report) {
+        let case = JunitCase {
+            name: "verification".to_string(),
+            failure: Some((err.to_string(), format!("{err:#}"))),
+            error: true,
+        };
+        self.suites
+            .push(JunitSuite { name: format!("{contract_name} ({address})"), cases: vec![case] });
+    }
+
+    /// Renders the report as JUnit XML.
+    pub fn to_xml(&self) -> String {
+        let count = |suite: &JunitSuite, error: bool| {
+            suite.cases.iter().filter(|case| case.failure.is_some() && case.error == error).count()
+        };
+        let tests = self.suites.iter().map(|suite| suite.cases.len()).sum::<usize>();
+        let failures = self.suites.iter().map(|suite| count(suite, false)).sum::<usize>();
+        let errors = self.suites.iter().map(|suite| count(suite, true)).sum::<usize>();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"forge verify-bytecode\" tests=\"{tests}\" failures=\"{failures}\" errors=\"{errors}\">\n"
+        ));
+        for suite in &self.suites {
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+                xml_escape(&suite.name),
+                suite.cases.len(),
+                count(suite, false),
+                count(suite, true)
+            ));
+            for case in &suite.cases {
+                let name = xml_escape(&case.name);
+                let classname = xml_escape(&suite.name);
+                let Some((message, details)) = &case.failure else {
+                    xml.push_str(&format!(
+                        "    <testcase name=\"{name}\" classname=\"{classname}\"/>\n"
+                    ));
+                    continue
+                };
+                let tag = if case.error { "error" } else { "failure" };
+                xml.push_str(&format!(
+                    "    <testcase name=\"{name}\" classname=\"{classname}\">\n      <{tag} message=\"{}\">{}</{tag}>\n    </testcase>\n",
+                    xml_escape(message),
+                    xml_escape(details)
+                ));
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Escapes the characters which can't appear as is in XML text and attributes.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The parts of a `forge script` broadcast file needed to verify its deployments.
+#[derive(Debug, Deserialize)]
+pub struct Broadcast {
+    transactions: Vec<BroadcastTransaction>,
+    /// The libraries deployed by the script, in the form `<file>:<lib>:<address>`.
+    #[serde(default)]
+    pub libraries: Vec<String>,
+    pub chain: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BroadcastTransaction {
+    transaction_type: String,
+    contract_name: Option<String>,
+    contract_address: Option<Address>,
+    arguments: Option<Vec<String>>,
+}
+
+/// A contract deployed by a `forge script` broadcast.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BroadcastContract {
+    pub address: Address,
+    pub name: String,
+    pub arguments: Option<Vec<String>>,
+}
+
+impl Broadcast {
+    /// Returns the contracts deployed with `CREATE` or `CREATE2` whose name is known.
+    pub fn contracts(&self) -> Vec<BroadcastContract> {
+        self.transactions
+            .iter()
+            .filter(|tx| matches!(tx.transaction_type.as_str(), "CREATE" | "CREATE2"))
+            .filter_map(|tx| {
+                let name = tx.contract_name.clone().filter(|name| !name.is_empty())?;
+                Some(BroadcastContract {
+                    address: tx.contract_address?,
+                    name,
+                    arguments: tx.arguments.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// An entry of the `--db` ledger, which records a verification result with its context.
+#[derive(Debug, Serialize)]
+struct LedgerEntry<'a> {
+    /// The unix timestamp of the verification, in seconds.
+    timestamp: u64,
+    chain: Option<Chain>,
+    compiler: Option<&'a str>,
+    #[serde(flatten)]
+    result: &'a JsonResult,
+}
+
+/// The attestation written with `--certificate`.
+#[derive(Debug, Serialize)]
+pub struct Certificate<'a> {
+    pub address: Address,
+    pub chain: Option<Chain>,
+    pub contract_name: &'a str,
+    pub compiler_version: Option<&'a str>,
+    pub evm_version: Option<EvmVersion>,
+    pub optimizer: Option<bool>,
+    pub optimizer_runs: Option<usize>,
+    pub creation_tx_hash: Option<TxHash>,
+    pub onchain_code_hash: Option<B256>,
+    pub local_code_hash: Option<B256>,
+    /// The unix timestamp of the verification, in seconds.
+    pub timestamp: u64,
+    /// The verdict, as printed with `--quiet`. Only set with `--certificate-always`, as the
+    /// certificate otherwise implies a full match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<String>,
+}
+
+impl<'a> Certificate<'a> {
+    /// Collects the attested settings and hashes from the results of the verification.
+    pub fn new(
+        chain: Option<Chain>,
+        contract_name: &'a str,
+        json_results: &'a [JsonResult],
+        with_verdict: bool,
+    ) -> Result<Self> {
+        let first = json_results.first().ok_or_eyre("No verification results to certify")?;
+        Ok(Self {
+            address: first.address,
+            chain,
+            contract_name,
+            compiler_version: first.local_solc_version.as_deref(),
+            evm_version: first.evm_version,
+            optimizer: first.optimizer,
+            optimizer_runs: first.optimizer_runs,
+            creation_tx_hash: first.creation_tx_hash,
+            onchain_code_hash: json_results.iter().find_map(|res| res.onchain_code_hash),
+            local_code_hash: json_results.iter().find_map(|res| res.local_code_hash),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            verdict: with_verdict.then(|| quiet_verdict(json_results)),
+        })
+    }
+}
+
+/// Appends the results to the json lines ledger at the given path, creating it if needed.
+pub fn append_to_ledger(
+    path: &Path,
+    chain: Option<Chain>,
+    json_results: &[JsonResult],
+) -> Result<()> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut lines = String::new();
+    for result in json_results {
+        let compiler = result.local_solc_version.as_deref();
+        let entry = LedgerEntry { timestamp, chain, compiler, result };
+        lines.push_str(&serde_json::to_string(&entry)?);
+        lines.push('\n');
+    }
+
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        foundry_common::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+/// Prints the given results as a json array to stdout, sorted by address and bytecode type so that
+/// the output is deterministic.
+pub fn print_json_results(json_results: &mut [JsonResult]) -> Result<()> {
+    json_results.sort_by_key(|res| (res.address, res.bytecode_type));
+    println!("{}", serde_json::to_string(&json_results)?);
+    Ok(())
+}
+
+fn compare_bytecode_parts(
+    mut local_bytecode: &[u8],
+    mut bytecode: &[u8],
+    constructor_args: &[u8],
+    is_runtime: bool,
+) -> MatchVerdict {
+    let mut verdict = MatchVerdict::default();
+
+    // 1. Check length of constructor args
+    if !constructor_args.is_empty() && !is_runtime {
+        // If not runtime, extract constructor args from the end of the bytecode
+        if constructor_args.len() > bytecode.len() || constructor_args.len() > local_bytecode.len()
         {
-            maybe_predeploy = true;
-            Ok((None, maybe_predeploy))
+            return verdict
         }
-        // Ref: https://api.basescan.org/api?module=contract&action=getcontractcreation&contractaddresses=0xC0d3c0d3c0D3c0d3C0D3c0D3C0d3C0D3C0D30010&apiKey=YourAPIKey
-        Err(EtherscanError::Serde { error: _, content }) if content.contains("GENESIS") => {
-            maybe_predeploy = true;
-            Ok((None, maybe_predeploy))
+        let (local_code, local_args) =
+            local_bytecode.split_at(local_bytecode.len() - constructor_args.len());
+        let (code, args) = bytecode.split_at(bytecode.len() - constructor_args.len());
+        verdict.constructor_args_match = Some(local_args == args);
+        local_bytecode = local_code;
+        bytecode = code;
+    }
+
+    // 2. Assume metadata is at the end of the bytecode
+    let local_body = extract_metadata_hash(local_bytecode);
+    let body = extract_metadata_hash(bytecode);
+    let local_metadata = &local_bytecode[local_body.len()..];
+    let metadata = &bytecode[body.len()..];
+    if !local_metadata.is_empty() || !metadata.is_empty() {
+        verdict.metadata_match = Some(local_metadata == metadata);
+    }
+
+    // 3. Now compare the local code and bytecode
+    verdict.body_match = local_body == body;
+    verdict.similarity = similarity(local_body, body);
+
+    verdict
+}
+
+/// The maximum number of bytes by which the bytecodes are shifted when searching for an alignment.
+pub const MAX_ALIGNMENT_WINDOW: usize = 32;
+
+/// Searches for a shift of up to [MAX_ALIGNMENT_WINDOW] bytes at the start of either bytecode for
+/// which they match.
+///
+/// Returns the offset of the on-chain bytecode relative to the local one, positive if the local
+/// bytecode has extra leading bytes, along with the verdict which is downgraded to partial.
+pub fn align_bytecodes(
+    local_bytecode: &[u8],
+    bytecode: &[u8],
+    constructor_args: &[u8],
+    bytecode_hash: BytecodeHash,
+) -> Option<(i64, MatchVerdict)> {
+    for shift in 1..=MAX_ALIGNMENT_WINDOW {
+        let candidates = [
+            (shift as i64, local_bytecode.get(shift..), Some(bytecode)),
+            (-(shift as i64), Some(local_bytecode), bytecode.get(shift..)),
+        ];
+        for (offset, local, onchain) in candidates {
+            let (Some(local), Some(onchain)) = (local, onchain) else { continue };
+            let mut verdict =
+                match_bytecodes(local, onchain, constructor_args, false, bytecode_hash, false);
+            if verdict.match_type.is_some() {
+                verdict.match_type = Some(VerificationType::Partial);
+                return Some((offset, verdict))
+            }
         }
-        Err(e) => eyre::bail!("Error fetching creation data from verifier-url: {:?}", e),
     }
+    None
 }
 
-pub fn check_and_encode_args(
-    artifact: &CompactContractBytecode,
-    args: Vec<String>,
-) -> Result<Vec<u8>, eyre::ErrReport> {
-    if let Some(constructor) = artifact.abi.as_ref().and_then(|abi| abi.constructor()) {
-        if constructor.inputs.len() != args.len() {
-            eyre::bail!(
-                "Mismatch of constructor arguments length. Expected {}, got {}",
-                constructor.inputs.len(),
-                args.len()
-            );
+/// Returns the offset of the first byte which differs between both bytecodes.
+pub fn first_difference(local_bytecode: &[u8], bytecode: &[u8]) -> Option<usize> {
+    local_bytecode.iter().zip(bytecode).position(|(a, b)| a != b).or_else(|| {
+        (local_bytecode.len() != bytecode.len()).then(|| local_bytecode.len().min(bytecode.len()))
+    })
+}
+
+/// A run of bytes which differ between the local and the on-chain bytecode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffRun {
+    pub offset: usize,
+    pub local: Bytes,
+    pub onchain: Bytes,
+}
+
+/// Returns the runs of consecutive bytes which differ between the bytecodes.
+///
+/// The bytes past the end of the shorter bytecode are part of the last run.
+pub fn diff_runs(local_bytecode: &[u8], bytecode: &[u8]) -> Vec<DiffRun> {
+    let mut runs: Vec<DiffRun> = vec![];
+    let mut start = None;
+    let common = local_bytecode.len().min(bytecode.len());
+    for offset in 0..=common {
+        let differs = offset < common && local_bytecode[offset] != bytecode[offset];
+        match (start, differs) {
+            (None, true) => start = Some(offset),
+            (Some(run_start), false) => {
+                runs.push(DiffRun {
+                    offset: run_start,
+                    local: Bytes::copy_from_slice(&local_bytecode[run_start..offset]),
+                    onchain: Bytes::copy_from_slice(&bytecode[run_start..offset]),
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if local_bytecode.len() != bytecode.len() {
+        let tail_local = &local_bytecode[common..];
+        let tail_onchain = &bytecode[common..];
+        match runs.last_mut().filter(|run| run.offset + run.local.len() == common) {
+            Some(run) => {
+                run.local = [run.local.as_ref(), tail_local].concat().into();
+                run.onchain = [run.onchain.as_ref(), tail_onchain].concat().into();
+            }
+            None => runs.push(DiffRun {
+                offset: common,
+                local: Bytes::copy_from_slice(tail_local),
+                onchain: Bytes::copy_from_slice(tail_onchain),
+            }),
         }
-        encode_args(&constructor.inputs, &args).map(|args| DynSolValue::Tuple(args).abi_encode())
-    } else {
-        Ok(Vec::new())
     }
+    runs
 }
 
-pub fn check_explorer_args(source_code: ContractMetadata) -> Result<Bytes, eyre::ErrReport> {
-    if let Some(args) = source_code.items.first() {
-        Ok(args.constructor_arguments.clone())
-    } else {
-        eyre::bail!("No constructor arguments found from block explorer");
+/// Maps an offset in the given bytecode back to the source location of its instruction, using the
+/// source map of the bytecode.
+///
+/// Returns the path of the source file and the line, starting at 1.
+pub fn source_location(
+    bytecode: &[u8],
+    source_map: &str,
+    offset: usize,
+    sources: &BTreeMap<u32, PathBuf>,
+) -> Option<(PathBuf, usize)> {
+    let pc_ic_map = PcIcMap::new(bytecode);
+    // The offset may point into the immediate of a PUSH, use the instruction it belongs to.
+    let ic =
+        (0..=offset.min(bytecode.len().checked_sub(1)?)).rev().find_map(|pc| pc_ic_map.get(pc))?;
+
+    let source_map = sourcemap::parse(source_map).ok()?;
+    let element = source_map.get(ic)?;
+    let path = sources.get(&element.index()?)?;
+    let content = foundry_common::fs::read_to_string(path).ok()?;
+    let start = (element.offset() as usize).min(content.len());
+    let line = content.as_bytes()[..start].iter().filter(|byte| **byte == b'\n').count() + 1;
+    Some((path.clone(), line))
+}
+
+/// Returns the ratio of bytes which are identical at the same position in both bytecodes, over the
+/// length of the longer one.
+fn similarity(local_bytecode: &[u8], bytecode: &[u8]) -> f64 {
+    let len = local_bytecode.len().max(bytecode.len());
+    if len == 0 {
+        return 1.0
     }
+    let matching = local_bytecode.iter().zip(bytecode).filter(|(a, b)| a == b).count();
+    matching as f64 / len as f64
 }
 
-pub fn check_args_len(
-    artifact: &CompactContractBytecode,
-    args: &Bytes,
-) -> Result<(), eyre::ErrReport> {
-    if let Some(constructor) = artifact.abi.as_ref().and_then(|abi| abi.constructor()) {
-        if !constructor.inputs.is_empty() && args.len() == 0 {
-            eyre::bail!(
-                "Contract expects {} constructor argument(s), but none were provided",
-                constructor.inputs.len()
-            );
+/// Returns the entries of the CBOR metadata at the end of the bytecode, if any.
+fn metadata_entries(bytecode: &[u8]) -> Option<Vec<(ciborium::Value, ciborium::Value)>> {
+    let body = extract_metadata_hash(bytecode);
+    let metadata = bytecode.get(body.len()..bytecode.len().checked_sub(2)?)?;
+    let ciborium::Value::Map(entries) = ciborium::from_reader(metadata).ok()? else { return None };
+    Some(entries)
+}
+
+/// Returns the features enabled with `pragma experimental` in the given source, deduplicated and
+/// sorted.
+pub fn experimental_pragmas(source: &str) -> Vec<String> {
+    // Ignore the pragmas which are commented out.
+    let code = strip_comments(source);
+    code.split(';')
+        .filter_map(|statement| {
+            let mut words =
+                statement.split_whitespace().skip_while(|word| *word != "pragma").skip(1);
+            (words.next()? == "experimental").then(|| words.next()).flatten()
+        })
+        .map(|feature| feature.trim_matches('"').to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Returns the source without its `//` and `/* */` comments, a block comment being replaced by a
+/// space as it separates the surrounding tokens.
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut rest = source;
+    loop {
+        let line_comment = rest.find("//");
+        let block_comment = rest.find("/*");
+        match (line_comment, block_comment) {
+            (Some(start), block) if block.map_or(true, |block| start < block) => {
+                code.push_str(&rest[..start]);
+                rest = rest[start..].find('\n').map_or("", |end| &rest[start + end..]);
+            }
+            (_, Some(start)) => {
+                code.push_str(&rest[..start]);
+                code.push(' ');
+                rest = rest[start + 2..].find("*/").map_or("", |end| &rest[start + 2 + end + 2..]);
+            }
+            _ => {
+                code.push_str(rest);
+                return code
+            }
         }
     }
-    Ok(())
 }
 
-pub async fn get_tracing_executor(
-    fork_config: &mut Config,
-    fork_blk_num: u64,
-    evm_version: EvmVersion,
-    evm_opts: EvmOpts,
-) -> Result<(Env, TracingExecutor)> {
-    fork_config.fork_block_number = Some(fork_blk_num);
-    fork_config.evm_version = evm_version;
+/// Extracts the solc version from the CBOR metadata at the end of the bytecode, if any.
+pub fn extract_solc_version(bytecode: &[u8]) -> Option<Version> {
+    let entries = metadata_entries(bytecode)?;
+    let (_, version) = entries.into_iter().find(|(key, _)| key.as_text() == Some("solc"))?;
+    match version {
+        // Releases are encoded as `[major, minor, patch]`.
+        ciborium::Value::Bytes(version) => match version[..] {
+            [major, minor, patch] => Some(Version::new(major.into(), minor.into(), patch.into())),
+            _ => None,
+        },
+        // Prereleases are encoded as the full version string.
+        ciborium::Value::Text(version) => Version::parse(&version).ok(),
+        _ => None,
+    }
+}
 
-    let (env, fork, _chain, is_alphanet) =
-        TracingExecutor::get_fork_material(fork_config, evm_opts).await?;
+/// The type of the metadata file digest embedded in the CBOR metadata.
+///
+/// Older compilers embed a swarm hash, `bzzr0` before solc 0.5.9 and `bzzr1` until the IPFS hash
+/// became the default in 0.6.0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataDigestType {
+    Ipfs,
+    Bzzr0,
+    Bzzr1,
+}
 
-    let executor = TracingExecutor::new(
-        env.clone(),
-        fork,
-        Some(fork_config.evm_version),
-        false,
-        false,
-        is_alphanet,
-    );
+impl fmt::Display for MetadataDigestType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ipfs => write!(f, "ipfs"),
+            Self::Bzzr0 => write!(f, "bzzr0"),
+            Self::Bzzr1 => write!(f, "bzzr1"),
+        }
+    }
+}
 
-    Ok((env, executor))
+/// The metadata file digest embedded in the CBOR metadata of a bytecode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataDigest {
+    pub digest_type: MetadataDigestType,
+    pub digest: Bytes,
 }
 
-pub fn configure_env_block(env: &mut Env, block: &Block) {
-    env.block.timestamp = U256::from(block.header.timestamp);
-    env.block.coinbase = block.header.miner;
-    env.block.difficulty = block.header.difficulty;
-    env.block.prevrandao = Some(block.header.mix_hash.unwrap_or_default());
-    env.block.basefee = U256::from(block.header.base_fee_per_gas.unwrap_or_default());
-    env.block.gas_limit = U256::from(block.header.gas_limit);
+impl fmt::Display for MetadataDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.digest_type, self.digest)
+    }
 }
 
-pub fn deploy_contract(
-    executor: &mut TracingExecutor,
-    env: &Env,
-    spec_id: SpecId,
-    transaction: &Transaction,
-) -> Result<Address, eyre::ErrReport> {
-    let env_with_handler = EnvWithHandlerCfg::new(Box::new(env.clone()), HandlerCfg::new(spec_id));
+/// Extracts the metadata file digest from the CBOR metadata at the end of the bytecode, if any.
+pub fn extract_metadata_digest(bytecode: &[u8]) -> Option<MetadataDigest> {
+    metadata_entries(bytecode)?.into_iter().find_map(|(key, value)| {
+        let digest_type = match key.as_text()? {
+            "ipfs" => MetadataDigestType::Ipfs,
+            "bzzr0" => MetadataDigestType::Bzzr0,
+            "bzzr1" => MetadataDigestType::Bzzr1,
+            _ => return None,
+        };
+        let digest = value.into_bytes().ok()?.into();
+        Some(MetadataDigest { digest_type, digest })
+    })
+}
 
-    if let Some(to) = transaction.to {
-        if to != DEFAULT_CREATE2_DEPLOYER {
-            eyre::bail!("Transaction `to` address is not the default create2 deployer i.e the tx is not a contract creation tx.");
+/// @dev This assumes that the metadata is at the end of the bytecode
+fn extract_metadata_hash(bytecode: &[u8]) -> &[u8] {
+    &bytecode[..bytecode.len() - metadata_len(bytecode).unwrap_or_default()]
+}
+
+/// The keys of the entries of the CBOR metadata appended by solc.
+const METADATA_KEYS: [&str; 5] = ["ipfs", "bzzr0", "bzzr1", "solc", "experimental"];
+
+/// Returns the length of the CBOR metadata at the end of the bytecode, including the 2 bytes of
+/// its length.
+///
+/// The metadata must fully decode either to a map of the entries appended by solc, e.g. with
+/// `experimental: true` for contracts using experimental features, or to the array appended by
+/// vyper. Otherwise the tail of the bytecode isn't its metadata and `None` is returned.
+fn metadata_len(bytecode: &[u8]) -> Option<usize> {
+    let len_start = bytecode.len().checked_sub(2)?;
+    let len = u16::from_be_bytes([bytecode[len_start], bytecode[len_start + 1]]) as usize;
+    let mut metadata = bytecode.get(len_start.checked_sub(len)?..len_start)?;
+    let value = ciborium::from_reader::<ciborium::Value, _>(&mut metadata).ok()?;
+    let is_metadata = match value {
+        ciborium::Value::Map(entries) => entries
+            .iter()
+            .all(|(key, _)| key.as_text().is_some_and(|key| METADATA_KEYS.contains(&key))),
+        ciborium::Value::Array(_) => true,
+        _ => false,
+    };
+    (is_metadata && metadata.is_empty()).then_some(len + 2)
+}
+
+/// Parses the `Library` field of the block explorer's source code, a list of `<name>:<address>`
+/// pairs separated by `;` or `,`.
+pub fn parse_explorer_libraries(library: &str) -> Result<BTreeMap<String, Address>> {
+    library
+        .split([';', ','])
+        .map(str::trim)
+        .filter(|library| !library.is_empty())
+        .map(|library| {
+            let Some((name, address)) = library.split_once(':') else {
+                eyre::bail!("Invalid library `{library}` reported by the block explorer")
+            };
+            let address = address
+                .trim()
+                .parse()
+                .wrap_err_with(|| format!("Invalid address of library `{name}`"))?;
+            Ok((name.trim().to_string(), address))
+        })
+        .collect()
+}
+
+/// Returns the bytes of the linked bytecode.
+///
+/// If libraries are left to link, the error lists them with the offsets of their placeholders.
+pub fn linked_bytes(bytecode: Option<&CompactBytecode>) -> Result<Bytes> {
+    let bytecode = bytecode.ok_or_eyre("Artifact does not contain bytecode")?;
+    if let Some(bytes) = bytecode.object.as_bytes() {
+        return Ok(bytes.clone())
+    }
+    eyre::bail!(
+        "Unlinked bytecode is not supported for verification, use --libraries to link {}",
+        unlinked_libraries(bytecode).join(", ")
+    )
+}
+
+/// Describes the libraries left to link in the bytecode, e.g. `src/Lib.sol:Lib at offsets 1, 40`.
+pub fn unlinked_libraries(bytecode: &CompactBytecode) -> Vec<String> {
+    bytecode
+        .link_references
+        .iter()
+        .flat_map(|(file, libs)| {
+            libs.iter().map(move |(name, offsets)| {
+                let offsets = offsets.iter().map(|o| o.start.to_string()).collect::<Vec<_>>();
+                format!("{file}:{name} at offsets {}", offsets.join(", "))
+            })
+        })
+        .collect()
+}
+
+/// Links the library placeholders in the artifact's bytecodes, and returns the linked libraries.
+///
+/// Libraries are resolved by name from the ones reported by the block explorer, falling back to
+/// `--libraries`.
+pub fn link_artifact(
+    artifact: &mut CompactContractBytecode,
+    explorer_libraries: &BTreeMap<String, Address>,
+    libraries: &Libraries,
+) -> Result<BTreeMap<String, Address>> {
+    let references = artifact
+        .bytecode
+        .iter()
+        .chain(artifact.deployed_bytecode.iter().filter_map(|b| b.bytecode.as_ref()))
+        .flat_map(|bytecode| &bytecode.link_references)
+        .flat_map(|(file, libs)| libs.keys().map(move |name| (file.clone(), name.clone())))
+        .collect::<BTreeSet<_>>();
+
+    let mut linked = BTreeMap::new();
+    let mut missing = Vec::new();
+    for (file, name) in references {
+        let address = match explorer_libraries.get(&name) {
+            Some(address) => Some(*address),
+            None => libraries
+                .libs
+                .iter()
+                .filter(|(path, _)| Path::new(&file).ends_with(path) || path.ends_with(&file))
+                .find_map(|(_, libs)| libs.get(&name))
+                .map(|address| address.parse::<Address>())
+                .transpose()
+                .wrap_err_with(|| format!("Invalid address of library `{name}`"))?,
+        };
+        let Some(address) = address else {
+            missing.push(format!("{file}:{name}"));
+            continue;
+        };
+
+        if let Some(bytecode) = artifact.bytecode.as_mut() {
+            bytecode.link(&file, &name, address);
         }
-        let result = executor.transact_with_env(env_with_handler)?;
+        if let Some(bytecode) =
+            artifact.deployed_bytecode.as_mut().and_then(|b| b.bytecode.as_mut())
+        {
+            bytecode.link(&file, &name, address);
+        }
+        linked.insert(name, address);
+    }
 
-        trace!(transact_result = ?result.exit_reason);
-        if result.result.len() != 20 {
-            eyre::bail!(
-                "Failed to deploy contract on fork at block: call result is not exactly 20 bytes"
-            );
+    if !missing.is_empty() {
+        eyre::bail!(
+            "The addresses of the libraries {} are unknown, use --libraries to provide them",
+            missing.join(", ")
+        )
+    }
+
+    Ok(linked)
+}
+
+/// Zeroes out the immutable references in the given runtime bytecode.
+///
+/// Immutables are left as zeroes in the artifact's `deployedBytecode`, so masking them out in the
+/// on-chain code allows comparing both directly.
+pub fn mask_immutables(bytecode: &mut [u8], immutable_references: &BTreeMap<String, Vec<Offsets>>) {
+    for offsets in immutable_references.values().flatten() {
+        let start = offsets.start as usize;
+        let end = start + offsets.length as usize;
+        if let Some(slice) = bytecode.get_mut(start..end) {
+            slice.fill(0);
         }
+    }
+}
 
-        Ok(Address::from_slice(&result.result))
-    } else {
-        let deploy_result = executor.deploy_with_env(env_with_handler, None)?;
-        trace!(deploy_result = ?deploy_result.raw.exit_reason);
-        Ok(deploy_result.address)
+/// Zeroes out the given byte ranges of the bytecode.
+///
+/// Ranges exceeding the bytecode are masked up to its end.
+pub fn mask_ranges(bytecode: &mut [u8], ranges: &[MaskRange]) {
+    for range in ranges {
+        let start = range.start.min(bytecode.len());
+        let end = range.start.saturating_add(range.len).min(bytecode.len());
+        bytecode[start..end].fill(0);
     }
 }
 
-pub async fn get_runtime_codes(
-    executor: &mut TracingExecutor,
-    provider: &RetryProvider,
-    address: Address,
-    fork_address: Address,
-    block: Option<u64>,
-) -> Result<(Bytecode, Bytes)> {
-    let fork_runtime_code = executor
-        .backend_mut()
-        .basic(fork_address)?
-        .ok_or_else(|| {
-            eyre::eyre!(
-                "Failed to get runtime code for contract deployed on fork at address {}",
-                fork_address
-            )
-        })?
-        .code
-        .ok_or_else(|| {
-            eyre::eyre!(
-                "Bytecode does not exist for contract deployed on fork at address {}",
-                fork_address
-            )
-        })?;
+/// The length of the `PUSH20 <address> ADDRESS EQ` call guard at the start of the runtime code of
+/// a library.
+const LIBRARY_GUARD_LEN: usize = 23;
 
-    let onchain_runtime_code = if let Some(block) = block {
-        provider.get_code_at(address).block_id(BlockId::number(block)).await?
-    } else {
-        provider.get_code_at(address).await?
+/// Returns the range of the address in the call guard at the start of the runtime code, if it
+/// starts with one.
+///
+/// The guard makes the library revert on calls which aren't delegate calls. Its address is zeroed
+/// in the artifact and set to the address of the library when it's deployed.
+fn library_guard_address(runtime_code: &[u8]) -> Option<std::ops::Range<usize>> {
+    let guard = runtime_code.get(..LIBRARY_GUARD_LEN)?;
+    (guard[0] == 0x73 && guard[21..] == [0x30, 0x14]).then_some(1..21)
+}
+
+/// Zeroes out the address of the library call guard in both runtime codes, if the deployed
+/// bytecode of the artifact is a library's.
+///
+/// Returns whether the address was masked.
+pub fn mask_library_address(
+    artifact_runtime_code: &[u8],
+    local_runtime_code: &mut [u8],
+    onchain_runtime_code: &mut [u8],
+) -> bool {
+    let is_library = library_guard_address(artifact_runtime_code)
+        .is_some_and(|range| artifact_runtime_code[range].iter().all(|byte| *byte == 0));
+    let (Some(local), Some(onchain)) =
+        (library_guard_address(local_runtime_code), library_guard_address(onchain_runtime_code))
+    else {
+        return false
     };
+    if !is_library {
+        return false
+    }
+    local_runtime_code[local].fill(0);
+    onchain_runtime_code[onchain].fill(0);
+    true
+}
 
-    Ok((fork_runtime_code, onchain_runtime_code))
+/// The result of comparing an immutable of the on-chain runtime code against its expected value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImmutableCheck {
+    pub id: String,
+    pub expected: B256,
+    /// The value found at each of the immutable's references.
+    pub actual: Vec<B256>,
+    pub matches: bool,
 }
 
-/// Returns `true` if the URL only consists of host.
+/// Reads the expected immutables from the on-chain runtime code at the artifact's references.
 ///
-/// This is used to check user input url for missing /api path
-#[inline]
-pub fn is_host_only(url: &Url) -> bool {
-    matches!(url.path(), "/" | "")
+/// An immutable only matches if each of its references holds the expected value.
+pub fn check_immutables(
+    code: &[u8],
+    immutable_references: &BTreeMap<String, Vec<Offsets>>,
+    expected_immutables: &[ExpectedImmutable],
+) -> Result<Vec<ImmutableCheck>> {
+    expected_immutables
+        .iter()
+        .map(|expected| {
+            let Some(offsets) = immutable_references.get(&expected.id) else {
+                eyre::bail!(
+                    "The artifact does not reference an immutable with id {}, available ids: {}",
+                    expected.id,
+                    immutable_references.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            };
+            let actual = offsets
+                .iter()
+                .map(|offsets| {
+                    let start = offsets.start as usize;
+                    let end = start + offsets.length as usize;
+                    let Some(value) = code.get(start..end).filter(|value| value.len() <= 32) else {
+                        eyre::bail!(
+                            "The immutable reference {start}..{end} of {} is out of bounds of the on-chain code",
+                            expected.id
+                        )
+                    };
+                    Ok(B256::left_padding_from(value))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let matches = actual.iter().all(|value| *value == expected.value);
+            Ok(ImmutableCheck { id: expected.id.clone(), expected: expected.value, actual, matches })
+        })
+        .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The result of comparing the address of a library called by the on-chain runtime code against
+/// the address it was linked to locally.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryCheck {
+    pub name: String,
+    pub expected: Address,
+    /// The address found at each of the library's link references.
+    pub actual: Vec<Address>,
+    pub matches: bool,
+}
 
-    #[test]
-    fn test_host_only() {
-        assert!(!is_host_only(&Url::parse("https://blockscout.net/api").unwrap()));
-        assert!(is_host_only(&Url::parse("https://blockscout.net/").unwrap()));
+/// Returns the offsets of the placeholders of each library in the runtime code of the artifact.
+///
+/// Linking drops the link references, so they must be read before.
+pub fn runtime_link_references(artifact: &CompactContractBytecode) -> BTreeMap<String, Vec<usize>> {
+    let mut references = BTreeMap::<String, Vec<usize>>::new();
+    let link_references = artifact
+        .deployed_bytecode
+        .iter()
+        .filter_map(|deployed_bytecode| deployed_bytecode.bytecode.as_ref())
+        .flat_map(|bytecode| bytecode.link_references.values());
+    for (name, offsets) in link_references.flatten() {
+        references
+            .entry(name.clone())
+            .or_default()
+            .extend(offsets.iter().map(|offsets| offsets.start as usize));
+    }
+    references
+}
+
+/// Masks the 20 byte addresses of the linked libraries in both runtime codes, so that only the
+/// code around them is compared.
+///
+/// The masked addresses of the on-chain code are checked against the ones the libraries were
+/// linked to locally instead.
+pub fn mask_linked_libraries(
+    local_bytecode: &mut [u8],
+    onchain_bytecode: &mut [u8],
+    references: &BTreeMap<String, Vec<usize>>,
+    linked_libraries: &BTreeMap<String, Address>,
+) -> Vec<LibraryCheck> {
+    references
+        .iter()
+        .filter_map(|(name, offsets)| {
+            let expected = *linked_libraries.get(name)?;
+            let mut actual = Vec::new();
+            for offset in offsets {
+                let range = *offset..*offset + 20;
+                if let Some(address) = onchain_bytecode.get_mut(range.clone()) {
+                    actual.push(Address::from_slice(address));
+                    address.fill(0);
+                }
+                if let Some(address) = local_bytecode.get_mut(range) {
+                    address.fill(0);
+                }
+            }
+            let matches = !actual.is_empty() && actual.iter().all(|address| *address == expected);
+            Some(LibraryCheck { name: name.clone(), expected, actual, matches })
+        })
+        .collect()
+}
+
+/// Returns the runtime bytecode of the artifact if it can be compared directly against the
+/// on-chain code, i.e. if it's fully linked and doesn't reference any immutables.
+pub fn static_runtime_code(artifact: &CompactContractBytecode) -> Option<Bytes> {
+    let deployed_bytecode = artifact.deployed_bytecode.as_ref()?;
+    if !deployed_bytecode.immutable_references.is_empty() {
+        return None
+    }
+    deployed_bytecode.bytecode.as_ref()?.to_owned().into_bytes()
+}
+
+/// The maximum distance from the end of the local creation code at which the end of the on-chain
+/// creation code's metadata is searched for.
+const MAX_METADATA_SHIFT: usize = 64;
+
+/// Returns the end of the CBOR metadata in the bytecode which is the closest to `expected_end`,
+/// within [MAX_METADATA_SHIFT] bytes.
+fn find_metadata_end(bytecode: &[u8], expected_end: usize) -> Option<usize> {
+    let start = expected_end.saturating_sub(MAX_METADATA_SHIFT).max(2);
+    let end = (expected_end + MAX_METADATA_SHIFT).min(bytecode.len());
+    (start..=end)
+        .filter(|end| is_metadata_end(bytecode, *end))
+        .min_by_key(|end| end.abs_diff(expected_end))
+}
+
+/// Returns whether the bytecode up to `end` ends with a solc CBOR metadata section, followed by its
+/// length.
+fn is_metadata_end(bytecode: &[u8], end: usize) -> bool {
+    let Some(len) = bytecode.get(end - 2..end) else { return false };
+    let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+    let Some(mut metadata) =
+        (end - 2).checked_sub(len).and_then(|start| bytecode.get(start..end - 2))
+    else {
+        return false
+    };
+    let Ok(ciborium::Value::Map(entries)) = ciborium::from_reader(&mut metadata) else {
+        return false
+    };
+    metadata.is_empty() &&
+        entries.iter().any(|(key, _)| key.as_text().is_some_and(|key| METADATA_KEYS.contains(&key)))
+}
+
+/// Extracts the constructor arguments from the tail of the on-chain creation code.
+///
+/// They are assumed to start right after the metadata of the on-chain creation code, which is
+/// searched for around the end of the local creation code so that a metadata of a different
+/// length doesn't shift the arguments. Otherwise, they are assumed to start right after the local
+/// creation code.
+pub fn extract_constructor_args(creation_code: &[u8], local_code_len: usize) -> Result<Bytes> {
+    let args_start = find_metadata_end(creation_code, local_code_len).unwrap_or(local_code_len);
+    let Some(args) = creation_code.get(args_start..) else {
+        eyre::bail!(
+            "On-chain creation code ({} bytes) is shorter than the local creation code ({} bytes), cannot extract constructor arguments",
+            creation_code.len(),
+            local_code_len
+        )
+    };
+    Ok(Bytes::copy_from_slice(args))
+}
+
+/// Returns whether the creation code of the artifact embeds its runtime code.
+///
+/// This holds for solc's output, whose constructor copies the runtime code from the creation code,
+/// but not for assembly constructors returning code built some other way. The end of the local
+/// creation code then doesn't tell where the constructor args start.
+pub fn embeds_runtime_code(artifact: &CompactContractBytecode) -> bool {
+    let creation_code = artifact.bytecode.as_ref().and_then(|bytecode| bytecode.object.as_bytes());
+    let runtime_code = artifact
+        .deployed_bytecode
+        .as_ref()
+        .and_then(|deployed_bytecode| deployed_bytecode.bytecode.as_ref())
+        .and_then(|bytecode| bytecode.object.as_bytes());
+    let (Some(creation_code), Some(runtime_code)) = (creation_code, runtime_code) else {
+        return false
+    };
+    !runtime_code.is_empty() &&
+        creation_code.windows(runtime_code.len()).any(|code| code == &runtime_code[..])
+}
+
+/// Returns the error of a contract whose creation code doesn't [embed its runtime
+/// code](embeds_runtime_code), and whose constructor args must then be provided.
+pub fn runtime_code_not_embedded(contract: &str) -> eyre::Report {
+    eyre::eyre!(
+        "The creation code of {contract} does not embed its runtime code, e.g. because of an assembly constructor, so the constructor args cannot be extracted from the on-chain creation code. Provide them with --constructor-args or --encoded-constructor-args"
+    )
+}
+
+/// Returns the tuple of the constructor inputs of the artifact, which is empty if it has no
+/// constructor.
+pub fn constructor_types(artifact: &CompactContractBytecode) -> Result<DynSolType> {
+    let inputs = artifact
+        .abi
+        .as_ref()
+        .and_then(|abi| abi.constructor())
+        .map(|constructor| constructor.inputs.as_slice())
+        .unwrap_or_default();
+    let types = inputs
+        .iter()
+        .map(|input| DynSolType::parse(&input.selector_type()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(DynSolType::Tuple(types))
+}
+
+/// Checks that the constructor args extracted from the creation code are a plausible encoding of
+/// the constructor inputs of the artifact.
+///
+/// Everything following the local creation code is extracted, so any extra code, e.g. the init
+/// code of a child contract created by a factory, ends up in the args.
+pub fn check_extracted_args(artifact: &CompactContractBytecode, args: &[u8]) -> Result<()> {
+    let types = constructor_types(artifact)?;
+    let signature = types.sol_type_name();
+
+    let encoded_len = types.abi_decode_params(args).map(|value| value.abi_encode_params().len());
+    match encoded_len {
+        Ok(len) if len == args.len() => Ok(()),
+        Ok(len) => eyre::bail!(
+            "Extracted {} bytes of constructor arguments, but the constructor inputs {signature} only encode to {len} bytes. The on-chain creation code likely contains more than the contract's code, e.g. the init code of contracts created by a factory, provide the arguments with --constructor-args or --encoded-constructor-args",
+            args.len()
+        ),
+        Err(err) => eyre::bail!(
+            "Extracted {} bytes of constructor arguments which are not a valid encoding of the constructor inputs {signature}: {err}. Provide the arguments with --constructor-args or --encoded-constructor-args",
+            args.len()
+        ),
+    }
+}
+
+/// Decodes the constructor args of the artifact from the data of the first log with the given
+/// `topic0`, preferring the logs emitted by `emitter`.
+///
+/// The data must be exactly the encoding of the constructor inputs. Returns the args along with
+/// their decoded values.
+pub fn constructor_args_from_event<'a>(
+    artifact: &CompactContractBytecode,
+    logs: impl IntoIterator<Item = &'a Log>,
+    topic0: B256,
+    emitter: Address,
+) -> Result<(Vec<u8>, Vec<DynSolValue>)> {
+    let logs = logs.into_iter().filter(|log| log.topics().first() == Some(&topic0));
+    let (emitted, others): (Vec<_>, Vec<_>) = logs.partition(|log| log.address == emitter);
+    let Some(log) = emitted.into_iter().chain(others).next() else {
+        eyre::bail!("The creation tx did not emit any event with topic0 {topic0}")
+    };
+
+    let types = constructor_types(artifact)?;
+    let signature = types.sol_type_name();
+
+    let data = &log.data.data;
+    let values = types.abi_decode_params(data).map_err(|err| {
+        eyre::eyre!(
+            "The data of the event emitted by {} is not an encoding of the constructor inputs {signature}: {err}",
+            log.address
+        )
+    })?;
+    let args = values.abi_encode_params();
+    if args.len() != data.len() {
+        eyre::bail!(
+            "The data of the event emitted by {} holds {} bytes, but the constructor inputs {signature} only encode to {} bytes",
+            log.address,
+            data.len(),
+            args.len()
+        )
+    }
+    let DynSolValue::Tuple(values) = values else { unreachable!("decoded a tuple") };
+    Ok((args, values))
+}
+
+/// Layout of the calldata of a CREATE2 factory call: the salt followed by the init code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Create2Layout {
+    /// Offset of the 32 byte salt in the calldata.
+    pub salt_offset: usize,
+    /// Offset of the init code in the calldata, which is assumed to span until its end.
+    pub initcode_offset: usize,
+}
+
+impl Default for Create2Layout {
+    /// The layout of the [DEFAULT_CREATE2_DEPLOYER] calldata, i.e. `salt ++ initcode`.
+    fn default() -> Self {
+        Self { salt_offset: 0, initcode_offset: 32 }
+    }
+}
+
+impl Create2Layout {
+    /// Resolves the layout of the calldata sent to `to`, using the default layout for the
+    /// [DEFAULT_CREATE2_DEPLOYER] unless a custom one is provided.
+    pub fn resolve(to: Option<Address>, custom: Option<Self>) -> Option<Self> {
+        match to {
+            Some(to) if to == DEFAULT_CREATE2_DEPLOYER => Some(custom.unwrap_or_default()),
+            Some(_) => custom,
+            None => None,
+        }
+    }
+
+    /// Validates the layout against the given calldata.
+    pub fn validate(&self, input: &[u8]) -> Result<()> {
+        if self.salt_offset + 32 > self.initcode_offset {
+            eyre::bail!(
+                "Salt at offset {} overlaps with the init code at offset {}",
+                self.salt_offset,
+                self.initcode_offset
+            )
+        }
+        if self.initcode_offset > input.len() {
+            eyre::bail!(
+                "Init code offset {} is out of bounds of the factory calldata ({} bytes)",
+                self.initcode_offset,
+                input.len()
+            )
+        }
+        Ok(())
+    }
+
+    /// Returns the init code from the given calldata.
+    pub fn initcode<'a>(&self, input: &'a [u8]) -> Result<&'a [u8]> {
+        self.validate(input)?;
+        Ok(&input[self.initcode_offset..])
+    }
+
+    /// Returns the given calldata with its init code replaced by `initcode`.
+    pub fn replace_initcode(&self, input: &[u8], initcode: &[u8]) -> Result<Bytes> {
+        self.validate(input)?;
+        let mut new_input = input[..self.initcode_offset].to_vec();
+        new_input.extend_from_slice(initcode);
+        Ok(new_input.into())
+    }
+
+    /// Returns the given calldata with its salt replaced by `salt`.
+    pub fn replace_salt(&self, input: &[u8], salt: B256) -> Result<Bytes> {
+        self.validate(input)?;
+        let mut new_input = input.to_vec();
+        new_input[self.salt_offset..self.salt_offset + 32].copy_from_slice(salt.as_slice());
+        Ok(new_input.into())
+    }
+}
+
+/// Writes the local and on-chain bytecodes of the given type to `dir`, as
+/// `local_<type>.hex` and `onchain_<type>.hex`.
+pub fn dump_bytecodes(
+    dir: &Path,
+    bytecode_type: BytecodeType,
+    local_bytecode: &[u8],
+    onchain_bytecode: &[u8],
+) -> Result<()> {
+    let name = match bytecode_type {
+        BytecodeType::Creation => "creation",
+        BytecodeType::Runtime => "runtime",
+    };
+    foundry_common::fs::create_dir_all(dir)?;
+    foundry_common::fs::write(
+        dir.join(format!("local_{name}.hex")),
+        hex::encode_prefixed(local_bytecode),
+    )?;
+    foundry_common::fs::write(
+        dir.join(format!("onchain_{name}.hex")),
+        hex::encode_prefixed(onchain_bytecode),
+    )?;
+    Ok(())
+}
+
+/// The creation data of a contract as reported by a block explorer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExplorerCreationData {
+    pub transaction_hash: TxHash,
+    pub constructor_args: Bytes,
+    /// The creation code, if the explorer stores it.
+    pub creation_code: Option<Bytes>,
+}
+
+impl ExplorerCreationData {
+    /// Fetches the creation data of the contract being verified from the given explorer.
+    pub async fn fetch(
+        client: &RateLimitedClient,
+        api_key: Option<&str>,
+        args: &VerifyBytecodeArgs,
+    ) -> Result<Self> {
+        let creation_data = client.contract_creation_data(args.address()).await?;
+        let source_code = client.contract_source_code(args.address()).await?;
+        let (metadata, _) = select_source_item(&source_code, &args.contract().name)?;
+        let creation_code =
+            client.contract_creation_code(api_key, args.address()).await.unwrap_or_default();
+        Ok(Self {
+            transaction_hash: creation_data.transaction_hash,
+            constructor_args: metadata.constructor_arguments.clone(),
+            creation_code,
+        })
+    }
+
+    /// Returns the parts on which both explorers disagree. The creation code is only compared if
+    /// both explorers store it.
+    pub fn discrepancies(&self, other: &Self) -> Vec<&'static str> {
+        let mut discrepancies = vec![];
+        if self.transaction_hash != other.transaction_hash {
+            discrepancies.push("creation tx");
+        }
+        if self.constructor_args != other.constructor_args {
+            discrepancies.push("constructor args");
+        }
+        if let (Some(creation_code), Some(other_creation_code)) =
+            (&self.creation_code, &other.creation_code)
+        {
+            if creation_code != other_creation_code {
+                discrepancies.push("creation code");
+            }
+        }
+        discrepancies
+    }
+}
+
+/// The EIP-1967 implementation slot, `keccak256("eip1967.proxy.implementation") - 1`.
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// The EIP-1967 beacon slot, `keccak256("eip1967.proxy.beacon") - 1`.
+pub const EIP1967_BEACON_SLOT: B256 =
+    b256!("a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50");
+
+/// The EIP-1967 admin slot, `keccak256("eip1967.proxy.admin") - 1`.
+pub const EIP1967_ADMIN_SLOT: B256 =
+    b256!("b53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103");
+
+/// The resolved addresses of an EIP-1967 proxy.
+#[derive(Clone, Copy, Debug)]
+pub struct ResolvedProxy {
+    /// The logic contract.
+    pub implementation: Address,
+    /// The beacon the implementation was read from, for beacon proxies.
+    pub beacon: Option<Address>,
+    /// The admin read from the admin slot, if it's set.
+    pub admin: Option<Address>,
+}
+
+/// Resolves the implementation of the EIP-1967 proxy at `proxy`.
+///
+/// The implementation slot is read first. If it's empty, the proxy is assumed to be a beacon
+/// proxy and the implementation is obtained by calling `implementation()` on the beacon. The admin
+/// is read from the admin slot along with it.
+pub async fn resolve_proxy(
+    provider: &RetryProvider,
+    proxy: Address,
+    block: BlockId,
+) -> Result<ResolvedProxy> {
+    let admin = provider.get_storage_at(proxy, EIP1967_ADMIN_SLOT.into()).block_id(block).await?;
+    let admin = Some(Address::from_word(admin.into())).filter(|admin| !admin.is_zero());
+
+    let implementation =
+        provider.get_storage_at(proxy, EIP1967_IMPLEMENTATION_SLOT.into()).block_id(block).await?;
+    let implementation = Address::from_word(implementation.into());
+    if !implementation.is_zero() {
+        return Ok(ResolvedProxy { implementation, beacon: None, admin })
+    }
+
+    let beacon = provider.get_storage_at(proxy, EIP1967_BEACON_SLOT.into()).block_id(block).await?;
+    let beacon = Address::from_word(beacon.into());
+    if beacon.is_zero() {
+        eyre::bail!(
+            "{proxy} is not an EIP-1967 proxy, both its implementation and beacon slots are empty"
+        )
+    }
+
+    // `implementation()`
+    let input = Bytes::from_static(&[0x5c, 0x60, 0xda, 0x1b]);
+    let tx = WithOtherFields::new(TransactionRequest::default().to(beacon).input(input.into()));
+    let result = provider.call(&tx).block(block).await?;
+    if result.len() != 32 {
+        eyre::bail!("Failed to get the implementation from beacon {beacon} of proxy {proxy}")
+    }
+    let implementation = Address::from_word(B256::from_slice(&result));
+
+    Ok(ResolvedProxy { implementation, beacon: Some(beacon), admin })
+}
+
+/// Reads the runtime code hash published for `address` by the registry, with its
+/// `codeHashOf(address)`.
+pub async fn fetch_registry_code_hash(
+    provider: &RetryProvider,
+    registry: Address,
+    address: Address,
+    block: BlockId,
+) -> Result<B256> {
+    let selector = Selector::from_slice(&keccak256("codeHashOf(address)")[..4]);
+    let input = Bytes::from([selector.as_slice(), address.into_word().as_slice()].concat());
+    let tx = WithOtherFields::new(TransactionRequest::default().to(registry).input(input.into()));
+    let result = provider.call(&tx).block(block).await?;
+    if result.len() != 32 {
+        eyre::bail!("Failed to read the code hash of {address} from registry {registry}")
+    }
+    let code_hash = B256::from_slice(&result);
+    if code_hash.is_zero() {
+        eyre::bail!("Registry {registry} does not publish a code hash for {address}")
+    }
+    Ok(code_hash)
+}
+
+/// A facet of an EIP-2535 diamond and the selectors it serves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Facet {
+    pub address: Address,
+    pub selectors: Vec<Selector>,
+}
+
+/// Fetches the facets of the diamond with the loupe's `facets()`.
+pub async fn fetch_facets(
+    provider: &RetryProvider,
+    diamond: Address,
+    block: BlockId,
+) -> Result<Vec<Facet>> {
+    // `facets()`
+    let input = Bytes::from_static(&[0x7a, 0x0e, 0xd6, 0x27]);
+    let tx = WithOtherFields::new(TransactionRequest::default().to(diamond).input(input.into()));
+    let result = provider.call(&tx).block(block).await?;
+    decode_facets(&result)
+        .wrap_err_with(|| format!("Failed to get the facets of diamond {diamond}"))
+}
+
+/// Decodes the `(address,bytes4[])[]` returned by the loupe's `facets()`.
+pub fn decode_facets(data: &[u8]) -> Result<Vec<Facet>> {
+    let ty = DynSolType::Array(Box::new(DynSolType::Tuple(vec![
+        DynSolType::Address,
+        DynSolType::Array(Box::new(DynSolType::FixedBytes(4))),
+    ])));
+    let value = ty.abi_decode(data)?;
+
+    let mut facets = vec![];
+    for facet in value.as_array().unwrap_or_default() {
+        let [address, selectors] = facet.as_tuple().unwrap_or_default() else {
+            eyre::bail!("Invalid facet: {facet:?}")
+        };
+        let address = address.as_address().ok_or_eyre("Invalid facet address")?;
+        let selectors = selectors
+            .as_array()
+            .unwrap_or_default()
+            .iter()
+            .map(|selector| {
+                selector
+                    .as_fixed_bytes()
+                    .map(|(word, _)| Selector::from_slice(&word[..4]))
+                    .ok_or_eyre("Invalid facet selector")
+            })
+            .collect::<Result<_>>()?;
+        facets.push(Facet { address, selectors });
+    }
+    Ok(facets)
+}
+
+/// A disk cache of the block explorer and RPC responses a verification depends on, which allows
+/// repeating it with `--offline`.
+///
+/// Responses are fetched when online, and only cached with `--cache-responses`. They're only read
+/// from the cache when offline.
+#[derive(Debug)]
+pub struct ResponseCache {
+    /// The directory of the cached responses, if the cache directory is known.
+    dir: Option<PathBuf>,
+    chain: Chain,
+    address: Address,
+    offline: bool,
+    save: bool,
+}
+
+impl ResponseCache {
+    pub fn new(chain: Chain, address: Address, offline: bool, save: bool) -> Self {
+        let dir = Config::foundry_cache_dir().map(|dir| dir.join("verify-bytecode"));
+        Self { dir, chain, address, offline, save }
+    }
+
+    /// Returns the response with the given name, read from the cache if offline and fetched
+    /// otherwise.
+    pub async fn get_or_fetch<T>(
+        &self,
+        name: &str,
+        fetch: impl Future<Output = Result<T>>,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if self.offline {
+            let Some(path) = self.path(name) else {
+                eyre::bail!("Failed to resolve the cache directory, which is required by --offline")
+            };
+            if !path.exists() {
+                eyre::bail!(
+                    "The {name} response is not cached at {}, run once with --cache-responses to cache it",
+                    path.display()
+                )
+            }
+            return foundry_common::fs::read_json_file(&path)
+        }
+
+        let response = fetch.await?;
+        if let Some(path) = self.path(name).filter(|_| self.save) {
+            if let Err(err) = self.write(&path, &response) {
+                warn!("failed to cache the {name} response: {err}");
+            }
+        }
+        Ok(response)
+    }
+
+    /// Returns the path of the response with the given name, which is scoped to the chain and the
+    /// address of the contract.
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        let file = format!("{}-{}-{name}.json", self.chain.id(), self.address);
+        self.dir.as_ref().map(|dir| dir.join(file))
+    }
+
+    fn write<T: Serialize>(&self, path: &Path, response: &T) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            foundry_common::fs::create_dir_all(parent)?;
+        }
+        foundry_common::fs::write_json_file(path, response)
+    }
+}
+
+/// A temporary git worktree of the project checked out at a given revision, used by `--git-ref`.
+///
+/// The worktree is removed from the repository when dropped.
+#[derive(Debug)]
+pub struct GitWorktree {
+    /// The root of the repository the worktree was added to.
+    repo: PathBuf,
+    /// The path of the worktree.
+    path: PathBuf,
+    /// The project root within the worktree, which may be a subdirectory of the repository.
+    pub project_root: PathBuf,
+    /// The full commit hash the worktree is checked out at.
+    pub commit: String,
+    _dir: tempfile::TempDir,
+}
+
+impl GitWorktree {
+    /// Checks out `rev` of the repository containing `project_root` into a temporary worktree,
+    /// including its submodules.
+    pub fn new(project_root: &Path, rev: &str) -> Result<Self> {
+        let repo = Git::root_of(project_root)
+            .wrap_err_with(|| format!("{} is not in a git repository", project_root.display()))?;
+        let commit = Git::new(&repo).commit_hash(false, rev)?;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("worktree");
+        Git::new(&repo)
+            .cmd()
+            .args(["worktree", "add", "--detach"])
+            .arg(&path)
+            .arg(&commit)
+            .exec()
+            .wrap_err_with(|| format!("failed to check out {rev}"))?;
+        let worktree = Self {
+            project_root: path.join(project_root.strip_prefix(&repo).unwrap_or(Path::new(""))),
+            repo,
+            path,
+            commit,
+            _dir: dir,
+        };
+
+        if worktree.path.join(".gitmodules").is_file() {
+            Git::new(&worktree.path).quiet(true).submodule_update(
+                false,
+                false,
+                false,
+                true,
+                std::iter::empty::<&str>(),
+            )?;
+        }
+
+        Ok(worktree)
+    }
+}
+
+impl Drop for GitWorktree {
+    fn drop(&mut self) {
+        if let Err(err) = Git::new(&self.repo)
+            .cmd()
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .exec()
+        {
+            warn!("failed to remove the worktree at {}: {err}", self.path.display());
+        }
+    }
+}
+
+/// A project extracted from a source bundle into a temporary directory, used by
+/// `--source-bundle`.
+#[derive(Debug)]
+pub struct SourceBundle {
+    /// The project root within the bundle.
+    pub project_root: PathBuf,
+    _dir: tempfile::TempDir,
+}
+
+impl SourceBundle {
+    /// Extracts the zip or tar archive at the given path or URL, after checking its SHA-256 hash
+    /// if given.
+    ///
+    /// The project root is the top level of the archive if it contains a `foundry.toml`, or its
+    /// only directory otherwise.
+    pub async fn new(source: &str, sha256: Option<B256>, proxy: Option<&Url>) -> Result<Self> {
+        let (name, archive) = if source.starts_with("http://") || source.starts_with("https://") {
+            let url = Url::parse(source)?;
+            let name = url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|name| !name.is_empty())
+                .unwrap_or("bundle.tar")
+                .to_string();
+            let client = crate::etherscan::proxied_http_client(proxy)?;
+            let response = client.get(url).send().await?.error_for_status()?;
+            (name, response.bytes().await?.to_vec())
+        } else {
+            (source.to_string(), foundry_common::fs::read(source)?)
+        };
+
+        if let Some(expected) = sha256 {
+            let actual = B256::from_slice(&Sha256::digest(&archive));
+            if actual != expected {
+                eyre::bail!(
+                    "The source bundle {source} has the SHA-256 hash {actual}, expected {expected}"
+                )
+            }
+        }
+
+        let dir = tempfile::tempdir()?;
+        let extracted = dir.path().join("bundle");
+        foundry_common::fs::create_dir_all(&extracted)?;
+        extract_archive(&name, &archive, &extracted)
+            .wrap_err_with(|| format!("failed to extract {source}"))?;
+
+        let project_root = if extracted.join(Config::FILE_NAME).is_file() {
+            extracted
+        } else {
+            let entries = std::fs::read_dir(&extracted)?.collect::<Result<Vec<_>, _>>()?;
+            match entries.as_slice() {
+                [entry] if entry.path().join(Config::FILE_NAME).is_file() => entry.path(),
+                _ => eyre::bail!(
+                    "The source bundle {source} does not contain a {}",
+                    Config::FILE_NAME
+                ),
+            }
+        };
+
+        Ok(Self { project_root, _dir: dir })
+    }
+}
+
+/// Extracts the zip archive, if `name` has a `.zip` extension, or the optionally gzipped tar
+/// archive into `dest`.
+fn extract_archive(name: &str, archive: &[u8], dest: &Path) -> Result<()> {
+    if Path::new(name).extension().is_some_and(|ext| ext == "zip") {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive))?;
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i)?;
+            let path = dest.join(archive_entry_path(Path::new(entry.name()))?);
+            if entry.is_dir() {
+                foundry_common::fs::create_dir_all(&path)?;
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                foundry_common::fs::create_dir_all(parent)?;
+            }
+            std::io::copy(&mut entry, &mut foundry_common::fs::create_file(&path)?)?;
+        }
+        return Ok(())
+    }
+
+    let reader: Box<dyn std::io::Read + '_> = if archive.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::read::GzDecoder::new(archive))
+    } else {
+        Box::new(archive)
+    };
+    let mut tar = tar::Archive::new(reader);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        archive_entry_path(&path)?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            eyre::bail!("The source bundle entry {} is a link", path.display())
+        }
+        entry.unpack_in(dest)?;
+    }
+    Ok(())
+}
+
+/// Returns the path of an archive entry, failing if it's absolute or escapes the archive.
+fn archive_entry_path(path: &Path) -> Result<&Path> {
+    if path.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        eyre::bail!("The source bundle entry {} is outside of the bundle", path.display())
+    }
+    Ok(path)
+}
+
+/// The number of times a request rejected by the block explorer's rate limit is retried.
+const RATE_LIMIT_RETRIES: u32 = 5;
+
+/// The delay before the first retry of a rate limited request, doubled on every attempt.
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The limits shared by the verifications of a batch, each enforced independently.
+#[derive(Debug)]
+pub struct BatchLimits {
+    builds: Arc<Semaphore>,
+    forks: Arc<Semaphore>,
+    /// The schedule of the block explorer requests, shared by the clients of all verifications.
+    pub explorer_schedule: Arc<Mutex<Instant>>,
+}
+
+impl BatchLimits {
+    pub fn new(builds: usize, forks: usize) -> Self {
+        Self {
+            builds: Arc::new(Semaphore::new(builds.max(1))),
+            forks: Arc::new(Semaphore::new(forks.max(1))),
+            explorer_schedule: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Waits until a local build may be started.
+    pub async fn build_permit(&self) -> Result<OwnedSemaphorePermit> {
+        Ok(self.builds.clone().acquire_owned().await?)
+    }
+
+    /// Waits until a fork simulation may be started.
+    pub async fn fork_permit(&self) -> Result<OwnedSemaphorePermit> {
+        Ok(self.forks.clone().acquire_owned().await?)
+    }
+}
+
+/// The RPC provider and the block explorer clients kept by `--serve` across its requests, so that
+/// they aren't rebuilt for each verification.
+#[derive(Debug)]
+pub struct SharedClients {
+    provider: RetryProvider,
+    /// The block explorer clients by chain id.
+    explorers: Mutex<BTreeMap<u64, RateLimitedClient>>,
+}
+
+impl SharedClients {
+    pub fn new(provider: RetryProvider) -> Self {
+        Self { provider, explorers: Default::default() }
+    }
+
+    pub fn provider(&self) -> &RetryProvider {
+        &self.provider
+    }
+
+    /// Returns the block explorer client of the chain, building it on first use.
+    pub fn explorer(
+        &self,
+        chain: Chain,
+        build: impl FnOnce() -> Result<RateLimitedClient>,
+    ) -> Result<RateLimitedClient> {
+        let mut explorers = self.explorers.lock().unwrap();
+        if let Some(client) = explorers.get(&chain.id()) {
+            return Ok(client.clone())
+        }
+        let client = build()?;
+        explorers.insert(chain.id(), client.clone());
+        Ok(client)
+    }
+}
+
+/// A block explorer [Client] which spaces out its requests and retries them with backoff when they
+/// are rate limited.
+///
+/// Clones share the schedule of the requests.
+#[derive(Clone, Debug)]
+pub struct RateLimitedClient {
+    client: Client,
+    /// The client of the requests which the block explorer [Client] doesn't implement.
+    http: reqwest::Client,
+    /// The minimum delay between two requests, if limited.
+    interval: Option<Duration>,
+    /// The earliest time at which the next request may be sent.
+    next_request: Arc<Mutex<Instant>>,
+}
+
+impl RateLimitedClient {
+    pub fn new(client: Client, requests_per_second: Option<f64>) -> Result<Self> {
+        let interval = requests_per_second
+            .map(|rps| {
+                if !rps.is_finite() || rps <= 0.0 {
+                    eyre::bail!("--etherscan-rps must be a positive number, got {rps}")
+                }
+                Ok(Duration::from_secs_f64(1.0 / rps))
+            })
+            .transpose()?;
+        Ok(Self {
+            client,
+            http: reqwest::Client::new(),
+            interval,
+            next_request: Arc::new(Mutex::new(Instant::now())),
+        })
+    }
+
+    /// Sends the requests which the block explorer [Client] doesn't implement with the given
+    /// client, e.g. to go through the same proxy.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Spaces out the requests together with the other clients sharing the given schedule.
+    pub fn with_schedule(mut self, next_request: Arc<Mutex<Instant>>) -> Self {
+        self.next_request = next_request;
+        self
+    }
+
+    /// Waits until the next request may be sent according to the configured rate.
+    pub async fn throttle(&self) {
+        let Some(interval) = self.interval else { return };
+        let deadline = {
+            let mut next_request = self.next_request.lock().unwrap();
+            let deadline = (*next_request).max(Instant::now());
+            *next_request = deadline + interval;
+            deadline
+        };
+        tokio::time::sleep_until(deadline.into()).await;
+    }
+
+    pub fn etherscan_api_url(&self) -> &Url {
+        self.client.etherscan_api_url()
+    }
+
+    pub async fn contract_creation_data(
+        &self,
+        address: Address,
+    ) -> Result<ContractCreationData, EtherscanError> {
+        self.with_retry(|| self.client.contract_creation_data(address)).await
+    }
+
+    pub async fn contract_source_code(
+        &self,
+        address: Address,
+    ) -> Result<ContractMetadata, EtherscanError> {
+        self.with_retry(|| self.client.contract_source_code(address)).await
+    }
+
+    /// Fetches the creation bytecode stored by the block explorer for the contract at `address`.
+    ///
+    /// The creation data is fetched with [`Client::contract_creation_data`] first, which reports
+    /// the explorer's errors and rate limits. The creation bytecode isn't exposed by the client, so
+    /// it's then read from the raw `getcontractcreation` response, and is `None` if the explorer
+    /// doesn't include it.
+    pub async fn contract_creation_code(
+        &self,
+        api_key: Option<&str>,
+        address: Address,
+    ) -> Result<Option<Bytes>> {
+        self.contract_creation_data(address).await?;
+
+        let mut url = self.etherscan_api_url().clone();
+        url.query_pairs_mut()
+            .append_pair("module", "contract")
+            .append_pair("action", "getcontractcreation")
+            .append_pair("contractaddresses", &address.to_string());
+        if let Some(api_key) = api_key {
+            url.query_pairs_mut().append_pair("apikey", api_key);
+        }
+        self.throttle().await;
+        let response: CreationBytecodeResponse = self.http.get(url).send().await?.json().await?;
+        Ok(response
+            .result
+            .into_iter()
+            .next()
+            .and_then(|creation| creation.creation_bytecode)
+            .filter(|code| !code.is_empty()))
+    }
+
+    async fn with_retry<F, Fut, T>(&self, mut request: F) -> Result<T, EtherscanError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, EtherscanError>>,
+    {
+        let mut backoff = RATE_LIMIT_BACKOFF;
+        let mut retries = RATE_LIMIT_RETRIES;
+        loop {
+            self.throttle().await;
+            match request().await {
+                Err(EtherscanError::RateLimitExceeded) if retries > 0 => {
+                    retries -= 1;
+                    warn!("rate limited by the block explorer ({retries} tries remaining)");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// The `getcontractcreation` response of a block explorer, of which only the creation bytecode
+/// is read.
+#[derive(Debug, Deserialize)]
+struct CreationBytecodeResponse {
+    result: Vec<CreationBytecode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreationBytecode {
+    #[serde(default)]
+    creation_bytecode: Option<Bytes>,
+}
+
+/// An account of the `--prestate` file, in the format of the `prestateTracer`. Fields which aren't
+/// set keep their value from the fork.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PrestateAccount {
+    pub balance: Option<U256>,
+    pub nonce: Option<u64>,
+    pub code: Option<Bytes>,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Seeds the given accounts into the backend of the executor.
+pub fn apply_prestate(
+    executor: &mut TracingExecutor,
+    prestate: BTreeMap<Address, PrestateAccount>,
+) -> Result<()> {
+    for (address, account) in prestate {
+        let backend = executor.backend_mut();
+        let mut info = backend.basic(address)?.unwrap_or_default();
+        if let Some(balance) = account.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = account.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = account.code {
+            let code = Bytecode::new_raw(code);
+            info.code_hash = code.hash_slow();
+            info.code = Some(code);
+        }
+        backend.insert_account_info(address, info);
+
+        for (slot, value) in account.storage {
+            backend.insert_account_storage(address, slot, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the accounts and storage touched by the tx, as of before its execution, with the
+/// `prestateTracer` of `debug_traceTransaction`.
+pub async fn fetch_prestate(
+    provider: &RetryProvider,
+    tx_hash: TxHash,
+) -> Result<BTreeMap<Address, PrestateAccount>> {
+    let prestate = provider
+        .raw_request(
+            "debug_traceTransaction".into(),
+            (tx_hash, serde_json::json!({ "tracer": "prestateTracer" })),
+        )
+        .await
+        .wrap_err_with(|| format!("Failed to trace the prestate of tx {tx_hash}"))?;
+    Ok(prestate)
+}
+
+/// Adjusts the simulation of an OP Stack deposit transaction, which mints its `mint` value to the
+/// sender and doesn't pay for gas.
+///
+/// Returns whether the transaction is a deposit.
+pub fn apply_deposit_tx(
+    executor: &mut TracingExecutor,
+    env: &mut Env,
+    transaction: &WithOtherFields<Transaction>,
+) -> Result<bool> {
+    if transaction.transaction_type != Some(SYSTEM_TRANSACTION_TYPE) {
+        return Ok(false)
+    }
+
+    let mint = transaction.other.get_deserialized::<U256>("mint").transpose()?.unwrap_or_default();
+    let backend = executor.backend_mut();
+    let mut info = backend.basic(transaction.from)?.unwrap_or_default();
+    info.balance = info.balance.saturating_add(mint);
+    backend.insert_account_info(transaction.from, info);
+
+    env.tx.gas_price = U256::ZERO;
+    env.tx.gas_priority_fee = None;
+    env.block.basefee = U256::ZERO;
+    Ok(true)
+}
+
+/// Reads a hex encoded bytecode from the given file.
+pub fn read_bytecode_file(path: &Path) -> Result<Bytes> {
+    let content = foundry_common::fs::read_to_string(path)?;
+    hex::decode(content.trim())
+        .map(Into::into)
+        .wrap_err_with(|| format!("Invalid hex bytecode in file {}", path.display()))
+}
+
+/// The creation transaction of a contract and its receipt, which can be snapshotted to disk with
+/// `--save-inputs` and replayed with `--replay-inputs`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreationInputs {
+    pub transaction: WithOtherFields<Transaction>,
+    pub receipt: AnyTransactionReceipt,
+}
+
+impl CreationInputs {
+    /// Fetches the creation transaction with the given hash and its receipt.
+    pub async fn fetch(provider: &RetryProvider, tx_hash: TxHash) -> Result<Self> {
+        let transaction = provider
+            .get_transaction_by_hash(tx_hash)
+            .await
+            .or_else(|e| eyre::bail!("Couldn't fetch transaction from RPC: {:?}", e))?
+            .ok_or_else(|| eyre::eyre!("Transaction not found for hash {}", tx_hash))?;
+        let receipt = provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .or_else(|e| eyre::bail!("Couldn't fetch transaction receipt from RPC: {:?}", e))?;
+        let receipt = if let Some(receipt) = receipt {
+            receipt
+        } else {
+            eyre::bail!("Receipt not found for transaction hash {}", tx_hash);
+        };
+        Ok(Self { transaction, receipt })
+    }
+
+    /// Reads the snapshot of the transaction with the given hash from `dir`.
+    pub fn load(dir: &Path, tx_hash: TxHash) -> Result<Self> {
+        let path = Self::path(dir, tx_hash);
+        if !path.exists() {
+            eyre::bail!(
+                "The creation tx {tx_hash} is not snapshotted at {}, run once with --save-inputs to snapshot it",
+                path.display()
+            )
+        }
+        foundry_common::fs::read_json_file(&path)
+    }
+
+    /// Writes the snapshot to `dir`, keyed by the transaction hash.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        foundry_common::fs::create_dir_all(dir)?;
+        foundry_common::fs::write_json_file(&Self::path(dir, self.transaction.hash), self)
+    }
+
+    /// Extracts the creation code of the contract at `address` from the transaction input.
+    pub fn creation_code(
+        &self,
+        address: Address,
+        create2_layout: Option<Create2Layout>,
+    ) -> Result<Bytes> {
+        let Self { transaction, receipt } = self;
+        if receipt.to.is_none() && receipt.contract_address == Some(address) {
+            Ok(transaction.input.clone())
+        } else if let Some(layout) = Create2Layout::resolve(receipt.to, create2_layout) {
+            Ok(Bytes::copy_from_slice(layout.initcode(&transaction.input)?))
+        } else {
+            eyre::bail!("Could not extract the creation code for contract at address {}", address);
+        }
+    }
+
+    fn path(dir: &Path, tx_hash: TxHash) -> PathBuf {
+        dir.join(format!("{tx_hash}.json"))
+    }
+}
+
+/// Returns whether the creation code is the one sent by the transaction itself, i.e. its input
+/// for a CREATE tx or the init code of a CREATE2 factory call, rather than the one of a frame
+/// nested deeper in its call tree.
+pub fn is_top_level_creation(
+    transaction: &Transaction,
+    creation_code: &[u8],
+    create2_layout: Option<Create2Layout>,
+) -> bool {
+    match Create2Layout::resolve(transaction.to, create2_layout) {
+        Some(layout) => layout.initcode(&transaction.input).is_ok_and(|code| code == creation_code),
+        None => transaction.to.is_none() && transaction.input[..] == *creation_code,
+    }
+}
+
+/// Overrides the optimizer settings of the given config with the ones recorded by the block
+/// explorer.
+///
+/// Returns a description of each setting that differed from the local config.
+pub fn apply_etherscan_optimizer_settings(
+    config: &mut Config,
+    etherscan_settings: &Metadata,
+) -> Vec<String> {
+    let mut overrides: Vec<String> = vec![];
+    let optimizer = etherscan_settings.optimization_used == 1;
+    if config.optimizer != optimizer {
+        overrides.push(format!(
+            "Optimizer overridden from block explorer metadata: local={}, onchain={}",
+            config.optimizer, optimizer
+        ));
+        config.optimizer = optimizer;
+    }
+    if optimizer && config.optimizer_runs as u64 != etherscan_settings.runs {
+        overrides.push(format!(
+            "Optimizer runs overridden from block explorer metadata: local={}, onchain={}",
+            config.optimizer_runs, etherscan_settings.runs
+        ));
+        config.optimizer_runs = etherscan_settings.runs as usize;
+    }
+
+    overrides
+}
+
+fn find_mismatch_in_settings(
+    etherscan_settings: &Metadata,
+    local_settings: &Config,
+) -> Vec<String> {
+    let mut mismatches: Vec<String> = vec![];
+    if etherscan_settings.evm_version != local_settings.evm_version.to_string().to_lowercase() {
+        let str = format!(
+            "EVM version mismatch: local={}, onchain={}",
+            local_settings.evm_version, etherscan_settings.evm_version
+        );
+        mismatches.push(str);
+    }
+    let local_optimizer: u64 = if local_settings.optimizer { 1 } else { 0 };
+    if etherscan_settings.optimization_used != local_optimizer {
+        let str = format!(
+            "Optimizer mismatch: local={}, onchain={}",
+            local_settings.optimizer, etherscan_settings.optimization_used
+        );
+        mismatches.push(str);
+    }
+    if etherscan_settings.runs != local_settings.optimizer_runs as u64 {
+        let str = format!(
+            "Optimizer runs mismatch: local={}, onchain={}",
+            local_settings.optimizer_runs, etherscan_settings.runs
+        );
+        mismatches.push(str);
+    }
+
+    mismatches
+}
+
+pub fn maybe_predeploy_contract(
+    creation_data: Result<ContractCreationData, EtherscanError>,
+) -> Result<(Option<ContractCreationData>, bool), eyre::ErrReport> {
+    let mut maybe_predeploy = false;
+    match creation_data {
+        Ok(creation_data) => Ok((Some(creation_data), maybe_predeploy)),
+        // Ref: https://explorer.mode.network/api?module=contract&action=getcontractcreation&contractaddresses=0xC0d3c0d3c0D3c0d3C0D3c0D3C0d3C0D3C0D30010
+        Err(EtherscanError::EmptyResult { status, message })
+            if status == "1" && message == "OK" =>
+        {
+            maybe_predeploy = true;
+            Ok((None, maybe_predeploy))
+        }
+        // Ref: https://api.basescan.org/api?module=contract&action=getcontractcreation&contractaddresses=0xC0d3c0d3c0D3c0d3C0D3c0D3C0d3C0D3C0D30010&apiKey=YourAPIKey
+        Err(EtherscanError::Serde { error: _, content }) if content.contains("GENESIS") => {
+            maybe_predeploy = true;
+            Ok((None, maybe_predeploy))
+        }
+        Err(e) => eyre::bail!("Error fetching creation data from verifier-url: {:?}", e),
+    }
+}
+
+pub fn check_and_encode_args(
+    artifact: &CompactContractBytecode,
+    args: Vec<String>,
+) -> Result<Vec<u8>, eyre::ErrReport> {
+    if let Some(constructor) = artifact.abi.as_ref().and_then(|abi| abi.constructor()) {
+        if constructor.inputs.len() != args.len() {
+            eyre::bail!(
+                "Mismatch of constructor arguments length. Expected {}, got {}",
+                constructor.inputs.len(),
+                args.len()
+            );
+        }
+        // Parse each of the args to its declared type first, to report all of the invalid ones.
+        let mut values = Vec::with_capacity(args.len());
+        let mut errors = vec![];
+        for (index, (input, arg)) in constructor.inputs.iter().zip(&args).enumerate() {
+            let ty = input.selector_type();
+            match coerce_value(&ty, arg) {
+                Ok(value) => values.push(value),
+                Err(err) => {
+                    let name = Some(&input.name).filter(|name| !name.is_empty());
+                    let name = name.map(|name| format!(" ({name})")).unwrap_or_default();
+                    errors.push(format!("arg {index}{name}: expected {ty}, got {arg:?}: {err}"))
+                }
+            }
+        }
+        if !errors.is_empty() {
+            eyre::bail!("Invalid constructor arguments:\n{}", errors.join("\n"))
+        }
+        Ok(DynSolValue::Tuple(values).abi_encode())
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// Substitutes the constructor arguments at the positions given by the link args with their
+/// addresses.
+pub fn apply_link_args(args: &mut [String], link_args: &[LinkArg]) -> Result<()> {
+    for link_arg in link_args {
+        let len = args.len();
+        let arg = args.get_mut(link_arg.index).ok_or_else(|| {
+            eyre::eyre!(
+                "Link arg index {} is out of bounds for {} constructor argument(s)",
+                link_arg.index,
+                len
+            )
+        })?;
+        *arg = link_arg.address.to_string();
+    }
+    Ok(())
+}
+
+/// Selects the source code item of the contract with the given name, the block explorer may return
+/// several of them, e.g. for proxies.
+///
+/// Returns the selected item along with the number of items matching the name. If none of them
+/// matches, the first item is returned.
+pub fn select_source_item<'a>(
+    source_code: &'a ContractMetadata,
+    name: &str,
+) -> Result<(&'a Metadata, usize)> {
+    let first =
+        source_code.items.first().ok_or_eyre("No source code found on the block explorer")?;
+    let mut matching = source_code.items.iter().filter(|item| item.contract_name == name);
+    let Some(selected) = matching.next() else { return Ok((first, 0)) };
+    Ok((selected, 1 + matching.count()))
+}
+
+pub fn check_args_len(
+    artifact: &CompactContractBytecode,
+    args: &Bytes,
+) -> Result<(), eyre::ErrReport> {
+    if let Some(constructor) = artifact.abi.as_ref().and_then(|abi| abi.constructor()) {
+        if !constructor.inputs.is_empty() && args.len() == 0 {
+            eyre::bail!(
+                "Contract expects {} constructor argument(s), but none were provided",
+                constructor.inputs.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_tracing_executor(
+    fork_config: &mut Config,
+    fork_blk_num: u64,
+    evm_version: EvmVersion,
+    evm_opts: EvmOpts,
+) -> Result<(Env, TracingExecutor)> {
+    fork_config.fork_block_number = Some(fork_blk_num);
+    fork_config.evm_version = evm_version;
+
+    let (env, fork, _chain, is_alphanet) =
+        TracingExecutor::get_fork_material(fork_config, evm_opts).await?;
+
+    let executor = TracingExecutor::new(
+        env.clone(),
+        fork,
+        Some(fork_config.evm_version),
+        false,
+        false,
+        is_alphanet,
+    );
+
+    Ok((env, executor))
+}
+
+/// The gas limit of the genesis block if it's neither served by the RPC nor given with `--genesis`.
+pub const DEFAULT_GENESIS_GAS_LIMIT: u64 = 30_000_000;
+
+/// The parameters of a genesis block read with `--genesis`, in the format of a geth genesis file.
+///
+/// Missing parameters default to zero, or [DEFAULT_GENESIS_GAS_LIMIT] for the gas limit.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GenesisParams {
+    pub timestamp: U256,
+    pub coinbase: Address,
+    pub difficulty: U256,
+    pub mix_hash: B256,
+    pub base_fee_per_gas: U256,
+    pub gas_limit: U256,
+}
+
+impl Default for GenesisParams {
+    fn default() -> Self {
+        Self {
+            timestamp: U256::ZERO,
+            coinbase: Address::ZERO,
+            difficulty: U256::ZERO,
+            mix_hash: B256::ZERO,
+            base_fee_per_gas: U256::ZERO,
+            gas_limit: U256::from(DEFAULT_GENESIS_GAS_LIMIT),
+        }
+    }
+}
+
+impl GenesisParams {
+    /// Returns a block with the genesis parameters in its header.
+    pub fn into_block(self) -> Block {
+        let mut block = Block::default();
+        block.header.timestamp = self.timestamp.saturating_to();
+        block.header.miner = self.coinbase;
+        block.header.difficulty = self.difficulty;
+        block.header.mix_hash = Some(self.mix_hash);
+        block.header.base_fee_per_gas = Some(self.base_fee_per_gas.saturating_to());
+        block.header.gas_limit = self.gas_limit.saturating_to();
+        block
+    }
+}
+
+/// Configures the block env from the block header, only setting the fields which exist in the
+/// given spec.
+pub fn configure_env_block(env: &mut Env, block: &Block, spec_id: SpecId) {
+    env.block.timestamp = U256::from(block.header.timestamp);
+    env.block.coinbase = block.header.miner;
+    env.block.difficulty = block.header.difficulty;
+    // Before the merge the mix hash is part of the proof of work, and `DIFFICULTY` returned the
+    // actual difficulty, which is also what it should return when simulating with a post-merge
+    // spec.
+    env.block.prevrandao = if block.header.difficulty.is_zero() {
+        Some(block.header.mix_hash.unwrap_or_default())
+    } else {
+        Some(block.header.difficulty.into())
+    };
+    // A base fee on a pre-London simulation would alter the gas accounting.
+    env.block.basefee = if spec_id.is_enabled_in(SpecId::LONDON) {
+        U256::from(block.header.base_fee_per_gas.unwrap_or_default())
+    } else {
+        U256::ZERO
+    };
+    env.block.blob_excess_gas_and_price = spec_id.is_enabled_in(SpecId::CANCUN).then(|| {
+        BlobExcessGasAndPrice::new(block.header.excess_blob_gas.unwrap_or_default() as u64)
+    });
+    env.block.gas_limit = U256::from(block.header.gas_limit);
+}
+
+/// The outcome of simulating the deployment of the contract on a fork.
+#[derive(Clone, Copy, Debug)]
+pub struct ForkDeployment {
+    /// The address the contract was deployed at.
+    pub address: Address,
+    /// The gas used by the deployment transaction.
+    pub gas_used: u64,
+    /// Whether the deployment transaction halted successfully.
+    pub success: bool,
+}
+
+/// Executes the transaction of the env, and returns the init code of the contract at `address`
+/// from the CREATE or CREATE2 frame which created it.
+pub fn traced_creation_code(
+    executor: &mut TracingExecutor,
+    env: &Env,
+    spec_id: SpecId,
+    address: Address,
+) -> Result<Bytes> {
+    let env_with_handler = EnvWithHandlerCfg::new(Box::new(env.clone()), HandlerCfg::new(spec_id));
+    let result = executor.transact_with_env(env_with_handler)?;
+    let traces = result.traces.ok_or_eyre("The creation tx was not traced")?;
+    traces
+        .arena
+        .nodes()
+        .iter()
+        .map(|node| &node.trace)
+        .find(|trace| trace.kind.is_any_create() && trace.success && trace.address == address)
+        .map(|trace| trace.data.clone())
+        .ok_or_else(|| eyre::eyre!("The creation tx does not create a contract at {address}"))
+}
+
+pub fn deploy_contract(
+    executor: &mut TracingExecutor,
+    env: &Env,
+    spec_id: SpecId,
+    transaction: &Transaction,
+    custom_factory: bool,
+) -> Result<ForkDeployment, eyre::ErrReport> {
+    let env_with_handler = EnvWithHandlerCfg::new(Box::new(env.clone()), HandlerCfg::new(spec_id));
+
+    if let Some(to) = transaction.to {
+        if to != DEFAULT_CREATE2_DEPLOYER && !custom_factory {
+            eyre::bail!("Transaction `to` address is not the default create2 deployer i.e the tx is not a contract creation tx.");
+        }
+        let result = executor.transact_with_env(env_with_handler)?;
+
+        trace!(transact_result = ?result.exit_reason);
+        if result.reverted {
+            let reason = RevertDecoder::new().decode(&result.result, Some(result.exit_reason));
+            eyre::bail!(
+                "Failed to deploy contract on fork: the factory call reverted: {reason}. Use --prestate to seed any state it depends on"
+            );
+        }
+        let address = match result.result.len() {
+            // The default CREATE2 deployer returns the raw address.
+            20 => Address::from_slice(&result.result),
+            // Custom factories usually return the ABI encoded address.
+            32 if custom_factory => Address::from_slice(&result.result[12..]),
+            _ => eyre::bail!(
+                "Failed to deploy contract on fork at block: call result is not exactly 20 bytes"
+            ),
+        };
+        Ok(ForkDeployment {
+            address,
+            gas_used: result.gas_used,
+            success: result.exit_reason.is_ok(),
+        })
+    } else {
+        let deploy_result =
+            executor.deploy_with_env(env_with_handler, None).map_err(|err| match err {
+                EvmError::Execution(err) => eyre::eyre!(
+                    "Failed to deploy contract on fork: the constructor reverted: {}. Use --prestate to seed any state it depends on",
+                    err.reason
+                ),
+                err => err.into(),
+            })?;
+        trace!(deploy_result = ?deploy_result.raw.exit_reason);
+        Ok(ForkDeployment {
+            address: deploy_result.address,
+            gas_used: deploy_result.raw.gas_used,
+            success: deploy_result.raw.exit_reason.is_ok(),
+        })
+    }
+}
+
+pub async fn get_runtime_codes(
+    executor: &mut TracingExecutor,
+    provider: &RetryProvider,
+    address: Address,
+    fork_address: Address,
+    block: Option<u64>,
+) -> Result<(Bytecode, Bytes)> {
+    let fork_runtime_code = executor
+        .backend_mut()
+        .basic(fork_address)?
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Failed to get runtime code for contract deployed on fork at address {}",
+                fork_address
+            )
+        })?
+        .code
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Bytecode does not exist for contract deployed on fork at address {}",
+                fork_address
+            )
+        })?;
+
+    let onchain_runtime_code = if let Some(block) = block {
+        provider.get_code_at(address).block_id(BlockId::number(block)).await?
+    } else {
+        provider.get_code_at(address).await?
+    };
+
+    Ok((fork_runtime_code, onchain_runtime_code))
+}
+
+/// Parses a spec id by its name, e.g. `Cancun`, ignoring the case.
+///
+/// Unlike the `From<&str>` conversion of [SpecId], unknown names are rejected.
+pub fn parse_spec_id(s: &str) -> Result<SpecId, String> {
+    let specs = (0..=u8::MAX).filter_map(SpecId::n).collect::<Vec<_>>();
+    if let Some(spec) = specs.iter().find(|spec| <&str>::from(**spec).eq_ignore_ascii_case(s)) {
+        return Ok(*spec)
+    }
+    let names = specs.into_iter().map(<&str>::from).collect::<Vec<_>>();
+    Err(format!("Unknown spec id `{s}`, expected one of: {}", names.join(", ")))
+}
+
+/// A chain with a known block explorer API, listed with `--list-chains`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExplorerChain {
+    pub chain: Chain,
+    pub api_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_url: Option<String>,
+    /// Whether an API key is configured for the chain, either for its `[etherscan]` entry or with
+    /// `etherscan_api_key`.
+    pub api_key_configured: bool,
+}
+
+/// Returns the chains with a known block explorer API sorted by id, including the ones of the
+/// `[etherscan]` config entries, whose URLs take precedence.
+pub fn explorer_chains(config: &Config) -> Vec<ExplorerChain> {
+    let mut chains = BTreeMap::new();
+    for named in NamedChain::iter() {
+        let Some((api_url, browser_url)) = named.etherscan_urls() else { continue };
+        let chain = Chain::from(named);
+        chains.insert(
+            chain.id(),
+            ExplorerChain {
+                chain,
+                api_url: api_url.to_string(),
+                browser_url: Some(browser_url.to_string()),
+                api_key_configured: config.etherscan_api_key.is_some(),
+            },
+        );
+    }
+
+    for resolved in config.etherscan.clone().resolved().values().flatten() {
+        let Some(chain) = resolved.chain else { continue };
+        chains.insert(
+            chain.id(),
+            ExplorerChain {
+                chain,
+                api_url: resolved.api_url.clone(),
+                browser_url: resolved.browser_url.clone(),
+                api_key_configured: !resolved.key.is_empty() || config.etherscan_api_key.is_some(),
+            },
+        );
+    }
+
+    chains.into_values().collect()
+}
+
+/// Returns `true` if the URL only consists of host.
+///
+/// This is used to check user input url for missing /api path
+#[inline]
+pub fn is_host_only(url: &Url) -> bool {
+    matches!(url.path(), "/" | "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_only() {
+        assert!(!is_host_only(&Url::parse("https://blockscout.net/api").unwrap()));
+        assert!(is_host_only(&Url::parse("https://blockscout.net/").unwrap()));
         assert!(is_host_only(&Url::parse("https://blockscout.net").unwrap()));
     }
+
+    #[test]
+    fn test_extract_constructor_args() {
+        let creation_code = [0x60, 0x80, 0x01, 0x02];
+        assert_eq!(extract_constructor_args(&creation_code, 2).unwrap(), Bytes::from([0x01, 0x02]));
+        assert!(extract_constructor_args(&creation_code, 4).unwrap().is_empty());
+        assert!(extract_constructor_args(&creation_code, 5).is_err());
+
+        // `{"solc": 0.8.26}` followed by its length.
+        let metadata = [0xa1, 0x64, b's', b'o', b'l', b'c', 0x43, 0x00, 0x08, 0x1a, 0x00, 0x0a];
+        let creation_code = [&[0x60, 0x80][..], &metadata, &[0x01, 0x02]].concat();
+        assert_eq!(
+            extract_constructor_args(&creation_code, creation_code.len() - 2).unwrap(),
+            Bytes::from([0x01, 0x02])
+        );
+        // The local metadata may have a different length.
+        assert_eq!(
+            extract_constructor_args(&creation_code, creation_code.len() + 3).unwrap(),
+            Bytes::from([0x01, 0x02])
+        );
+        assert_eq!(
+            extract_constructor_args(&creation_code, creation_code.len() - 5).unwrap(),
+            Bytes::from([0x01, 0x02])
+        );
+    }
+
+    #[test]
+    fn test_hash_verdict() {
+        // `{"solc": 0.8.26}` followed by its length.
+        let metadata = [0xa1, 0x64, b's', b'o', b'l', b'c', 0x43, 0x00, 0x08, 0x1a, 0x00, 0x0a];
+        let local = [&[0x60, 0x80][..], &metadata].concat();
+        let mut onchain = local.clone();
+        onchain[10] = 0x09;
+
+        assert_ne!(normalized_code_hash(&local, false), normalized_code_hash(&onchain, false));
+        assert_eq!(normalized_code_hash(&local, true), normalized_code_hash(&onchain, true));
+        assert_eq!(normalized_code_hash(&local, true), keccak256([0x60, 0x80]));
+
+        let hash = normalized_code_hash(&local, false);
+        let verdict = hash_verdict(hash, hash, false, BytecodeHash::Ipfs).unwrap();
+        assert_eq!(verdict.match_type, Some(VerificationType::Full));
+        let verdict = hash_verdict(hash, hash, true, BytecodeHash::Ipfs).unwrap();
+        assert_eq!(verdict.match_type, Some(VerificationType::Partial));
+        assert!(hash_verdict(hash, B256::ZERO, false, BytecodeHash::Ipfs).is_none());
+    }
+
+    #[test]
+    fn test_embeds_runtime_code() {
+        let artifact = |creation_code: &str, runtime_code: &str| -> CompactContractBytecode {
+            serde_json::from_value(serde_json::json!({
+                "bytecode": { "object": creation_code, "linkReferences": {} },
+                "deployedBytecode": {
+                    "bytecode": { "object": runtime_code, "linkReferences": {} }
+                }
+            }))
+            .unwrap()
+        };
+        assert!(embeds_runtime_code(&artifact("0x6080aabbcc00", "0xaabbcc")));
+        assert!(embeds_runtime_code(&artifact("0xaabbcc", "0xaabbcc")));
+        // The runtime code is assembled by the constructor.
+        assert!(!embeds_runtime_code(&artifact("0x6080aabb00cc", "0xaabbcc")));
+        assert!(!embeds_runtime_code(&artifact("0x6080", "0x")));
+    }
+
+    #[test]
+    fn test_artifact_selector() {
+        use crate::types::ArtifactSelector;
+
+        let path = Path::new("out/Counter.sol/Counter.0.8.20.json");
+        let version = Version::parse("0.8.20+commit.a1b79de6").unwrap();
+        let selector: ArtifactSelector = "version=v0.8.20".parse().unwrap();
+        assert!(selector.matches(path, &version));
+        assert!(!selector.matches(path, &Version::new(0, 8, 19)));
+
+        let selector: ArtifactSelector = "path=Counter.0.8.20.json".parse().unwrap();
+        assert!(selector.matches(path, &Version::new(0, 8, 19)));
+        assert!(!selector.matches(Path::new("out/Counter.sol/Counter.json"), &version));
+        assert!("profile=default".parse::<ArtifactSelector>().is_err());
+        assert!("0.8.20".parse::<ArtifactSelector>().is_err());
+    }
+
+    #[test]
+    fn test_check_artifact_outputs() {
+        let artifact: CompactContractBytecode = serde_json::from_value(serde_json::json!({
+            "bytecode": { "object": "0x6080aabbcc", "linkReferences": {} }
+        }))
+        .unwrap();
+        assert!(check_artifact_outputs(&artifact, "Counter", true, false).is_ok());
+
+        let err = check_artifact_outputs(&artifact, "Counter", true, true).unwrap_err();
+        assert!(err.to_string().contains("lacks evm.deployedBytecode,"));
+
+        let empty: CompactContractBytecode = serde_json::from_value(serde_json::json!({
+            "bytecode": { "object": "0x", "linkReferences": {} }
+        }))
+        .unwrap();
+        let err = check_artifact_outputs(&empty, "Counter", true, true).unwrap_err();
+        assert!(err.to_string().contains("lacks evm.bytecode and evm.deployedBytecode,"));
+    }
+
+    /// Returns an artifact whose constructor takes the inputs of the given names and types.
+    fn constructor_artifact(inputs: &[(&str, &str)]) -> CompactContractBytecode {
+        let inputs = inputs
+            .iter()
+            .map(|(name, ty)| serde_json::json!({ "name": name, "type": ty, "internalType": ty }))
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({
+            "abi": [{ "type": "constructor", "stateMutability": "nonpayable", "inputs": inputs }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_and_encode_args() {
+        let artifact = constructor_artifact(&[("owner", "address"), ("", "uint256")]);
+        let args = |args: &[&str]| args.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let encoded =
+            check_and_encode_args(&artifact, args(&[&Address::ZERO.to_string(), "1"])).unwrap();
+        assert_eq!(encoded.len(), 64);
+
+        // Each of the invalid args is reported.
+        let err = check_and_encode_args(&artifact, args(&["0xzz", "one"])).unwrap_err().to_string();
+        assert!(err.contains(r#"arg 0 (owner): expected address, got "0xzz""#), "{err}");
+        assert!(err.contains(r#"arg 1: expected uint256, got "one""#), "{err}");
+        assert!(check_and_encode_args(&artifact, args(&["1"])).is_err());
+    }
+
+    #[test]
+    fn test_constructor_args_from_event() {
+        let artifact = constructor_artifact(&[("owner", "address"), ("cap", "uint256")]);
+        let emitter = Address::repeat_byte(0x11);
+        let topic0 = keccak256("Deployed(address,uint256)");
+        let encode = |cap: u64| {
+            DynSolValue::Tuple(vec![
+                DynSolValue::Address(Address::repeat_byte(0x22)),
+                DynSolValue::Uint(U256::from(cap), 256),
+            ])
+            .abi_encode_params()
+        };
+        let logs = [
+            Log::new_unchecked(Address::ZERO, vec![B256::ZERO], encode(1).into()),
+            Log::new_unchecked(Address::ZERO, vec![topic0], encode(2).into()),
+            Log::new_unchecked(emitter, vec![topic0], encode(3).into()),
+        ];
+
+        // The log emitted by the contract takes precedence.
+        let (args, values) =
+            constructor_args_from_event(&artifact, &logs, topic0, emitter).unwrap();
+        assert_eq!(args, encode(3));
+        assert_eq!(values[1], DynSolValue::Uint(U256::from(3), 256));
+        let (args, _) =
+            constructor_args_from_event(&artifact, &logs[..2], topic0, emitter).unwrap();
+        assert_eq!(args, encode(2));
+
+        assert!(constructor_args_from_event(&artifact, &logs[..1], topic0, emitter).is_err());
+        let log = Log::new_unchecked(emitter, vec![topic0], encode(3)[..32].to_vec().into());
+        assert!(constructor_args_from_event(&artifact, [&log], topic0, emitter).is_err());
+    }
+
+    #[test]
+    fn test_check_extracted_args() {
+        let artifact = constructor_artifact(&[("a", "uint256"), ("b", "bytes")]);
+        let args = DynSolValue::Tuple(vec![
+            DynSolValue::Uint(U256::from(1), 256),
+            DynSolValue::Bytes(vec![0x60, 0x80]),
+        ])
+        .abi_encode_params();
+        check_extracted_args(&artifact, &args).unwrap();
+
+        // Trailing code which isn't part of the encoding.
+        let err = check_extracted_args(&artifact, &[&args[..], &[0x60; 32]].concat()).unwrap_err();
+        assert!(err.to_string().contains("only encode to 128 bytes"), "{err}");
+        assert!(check_extracted_args(&artifact, &args[..32]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_ignored_bytecode() {
+        use BytecodeType::{Creation, Runtime};
+
+        assert_eq!(resolve_ignored_bytecode(None, &[]).unwrap(), None);
+        assert_eq!(resolve_ignored_bytecode(Some(Runtime), &[]).unwrap(), Some(Runtime));
+        assert_eq!(resolve_ignored_bytecode(None, &[Creation]).unwrap(), Some(Runtime));
+        assert_eq!(resolve_ignored_bytecode(None, &[Runtime]).unwrap(), Some(Creation));
+        assert_eq!(resolve_ignored_bytecode(None, &[Creation, Runtime]).unwrap(), None);
+        assert_eq!(resolve_ignored_bytecode(Some(Creation), &[Runtime]).unwrap(), Some(Creation));
+        assert!(resolve_ignored_bytecode(Some(Creation), &[Creation]).is_err());
+        assert!(resolve_ignored_bytecode(Some(Runtime), &[Creation, Runtime]).is_err());
+    }
+
+    #[test]
+    fn test_configure_env_block() {
+        let mix_hash = B256::repeat_byte(0x11);
+        let mut block = Block::default();
+        block.header.mix_hash = Some(mix_hash);
+
+        let mut env = Env::default();
+        configure_env_block(&mut env, &block, SpecId::CANCUN);
+        assert_eq!(env.block.difficulty, U256::ZERO);
+        assert_eq!(env.block.prevrandao, Some(mix_hash));
+
+        // Pre-merge block.
+        block.header.difficulty = U256::from(1234);
+        configure_env_block(&mut env, &block, SpecId::CANCUN);
+        assert_eq!(env.block.difficulty, U256::from(1234));
+        assert_eq!(env.block.prevrandao, Some(B256::from(U256::from(1234))));
+    }
+
+    #[test]
+    fn test_configure_env_block_spec() {
+        let mut block = Block::default();
+        block.header.base_fee_per_gas = Some(7);
+        block.header.excess_blob_gas = Some(0);
+
+        let mut env = Env::default();
+        configure_env_block(&mut env, &block, SpecId::CANCUN);
+        assert_eq!(env.block.basefee, U256::from(7));
+        assert!(env.block.blob_excess_gas_and_price.is_some());
+
+        configure_env_block(&mut env, &block, SpecId::SHANGHAI);
+        assert_eq!(env.block.basefee, U256::from(7));
+        assert!(env.block.blob_excess_gas_and_price.is_none());
+
+        configure_env_block(&mut env, &block, SpecId::BERLIN);
+        assert_eq!(env.block.basefee, U256::ZERO);
+    }
+
+    #[test]
+    fn test_is_zksync_chain() {
+        assert!(is_zksync_chain(Chain::from_id(324)));
+        assert!(!is_zksync_chain(Chain::mainnet()));
+    }
+
+    #[test]
+    fn test_explorer_chains() {
+        let mut config = Config::default();
+        let chains = explorer_chains(&config);
+        let mainnet = chains.iter().find(|chain| chain.chain == Chain::mainnet()).unwrap();
+        assert_eq!(mainnet.api_url, "https://api.etherscan.io/api");
+        assert!(!mainnet.api_key_configured);
+        assert!(chains.windows(2).all(|w| w[0].chain.id() < w[1].chain.id()));
+
+        config.etherscan_api_key = Some("key".to_string());
+        assert!(explorer_chains(&config).iter().all(|chain| chain.api_key_configured));
+    }
+
+    #[test]
+    fn test_parse_spec_id() {
+        assert_eq!(parse_spec_id("Cancun"), Ok(SpecId::CANCUN));
+        assert_eq!(parse_spec_id("shanghai"), Ok(SpecId::SHANGHAI));
+        assert!(parse_spec_id("Osaka2").unwrap_err().contains("Unknown spec id `Osaka2`"));
+    }
+
+    #[test]
+    fn test_genesis_params() {
+        let genesis: GenesisParams = serde_json::from_str(
+            r#"{
+                "timestamp": "0x5e9da7ce",
+                "coinbase": "0x00000000000000000000000000000000000000aa",
+                "gasLimit": "0x1c9c380",
+                "alloc": {}
+            }"#,
+        )
+        .unwrap();
+        let block = genesis.into_block();
+        assert_eq!(block.header.timestamp, 0x5e9da7ce);
+        assert_eq!(block.header.miner, Address::with_last_byte(0xaa));
+        assert_eq!(block.header.gas_limit, 30_000_000);
+        assert_eq!(block.header.base_fee_per_gas, Some(0));
+
+        let block = GenesisParams::default().into_block();
+        assert_eq!(block.header.gas_limit, DEFAULT_GENESIS_GAS_LIMIT as u128);
+    }
+
+    #[test]
+    fn test_match_short_bytecodes() {
+        assert!(!compare_bytecode_parts(&[0x60], &[0x60], &[0x01, 0x02], false).body_match);
+        assert!(compare_bytecode_parts(&[0x60], &[0x60], &[], true).body_match);
+    }
+
+    #[test]
+    fn test_match_verdict() {
+        // body ++ cbor metadata (`{}`) ++ metadata length ++ constructor args
+        let local = [0x60, 0x80, 0xa0, 0x00, 0x01, 0x01];
+        let onchain = [0x60, 0x80, 0xa0, 0x00, 0x01, 0x02];
+        let verdict = match_bytecodes(&local, &onchain, &[0x01], false, BytecodeHash::Ipfs, false);
+        assert_eq!(verdict.match_type, Some(VerificationType::Partial));
+        assert!(verdict.body_match);
+        assert_eq!(verdict.metadata_match, Some(true));
+        assert_eq!(verdict.constructor_args_match, Some(false));
+        assert_eq!(verdict.describe(), "opcodes match, metadata matches, constructor args differ");
+
+        let verdict = match_bytecodes(&local, &local, &[], true, BytecodeHash::Ipfs, false);
+        assert_eq!(verdict.match_type, Some(VerificationType::Full));
+        assert_eq!(verdict.constructor_args_match, None);
+
+        let verdict = match_bytecodes(&[0x60], &[0x61], &[], true, BytecodeHash::Ipfs, false);
+        assert_eq!(verdict.match_type, None);
+        assert_eq!(verdict.metadata_match, None);
+        assert_eq!(verdict.similarity, 0.0);
+    }
+
+    #[test]
+    fn test_parse_prestate() {
+        let prestate: BTreeMap<Address, PrestateAccount> = serde_json::from_str(
+            r#"{
+                "0x0000000000000000000000000000000000000042": {
+                    "code": "0x6080",
+                    "storage": { "0x1": "0x2" }
+                }
+            }"#,
+        )
+        .unwrap();
+        let account = &prestate[&Address::with_last_byte(0x42)];
+        assert_eq!(account.balance, None);
+        assert_eq!(account.code, Some(Bytes::from([0x60, 0x80])));
+        assert_eq!(account.storage, BTreeMap::from([(U256::from(1), U256::from(2))]));
+    }
+
+    #[test]
+    fn test_align_bytecodes() {
+        let local = [0x5b, 0x60, 0x80, 0x60, 0x40];
+        let onchain = [0x60, 0x80, 0x60, 0x40];
+        let (offset, verdict) = align_bytecodes(&local, &onchain, &[], BytecodeHash::Ipfs).unwrap();
+        assert_eq!(offset, 1);
+        assert_eq!(verdict.match_type, Some(VerificationType::Partial));
+
+        let (offset, _) = align_bytecodes(&onchain, &local, &[], BytecodeHash::Ipfs).unwrap();
+        assert_eq!(offset, -1);
+
+        assert!(align_bytecodes(&local, &[0x61, 0x80], &[], BytecodeHash::Ipfs).is_none());
+    }
+
+    #[test]
+    fn test_explorer_creation_data_discrepancies() {
+        let data = ExplorerCreationData {
+            transaction_hash: TxHash::with_last_byte(1),
+            constructor_args: Bytes::from([0x01]),
+            creation_code: Some(Bytes::from([0x60, 0x80])),
+        };
+        assert!(data.discrepancies(&data).is_empty());
+
+        let other = ExplorerCreationData { creation_code: None, ..data.clone() };
+        assert!(data.discrepancies(&other).is_empty());
+
+        let other = ExplorerCreationData {
+            constructor_args: Bytes::new(),
+            creation_code: Some(Bytes::from([0x60])),
+            ..data.clone()
+        };
+        assert_eq!(data.discrepancies(&other), ["constructor args", "creation code"]);
+    }
+
+    #[test]
+    fn test_creation_bytecode_response() {
+        let response: CreationBytecodeResponse = serde_json::from_value(serde_json::json!({
+            "status": "1",
+            "result": [{ "contractAddress": "0x01", "creationBytecode": "0x6080" }]
+        }))
+        .unwrap();
+        assert_eq!(response.result[0].creation_bytecode, Some(Bytes::from([0x60, 0x80])));
+
+        let response: CreationBytecodeResponse = serde_json::from_value(serde_json::json!({
+            "result": [{ "contractAddress": "0x01" }]
+        }))
+        .unwrap();
+        assert_eq!(response.result[0].creation_bytecode, None);
+    }
+
+    #[test]
+    fn test_ignore_trailing_zeros() {
+        let local = [0x60, 0x80, 0x00];
+        let onchain = [0x60, 0x80, 0x00, 0x00, 0x00];
+        let verdict = match_bytecodes(&local, &onchain, &[], true, BytecodeHash::Ipfs, true);
+        assert_eq!(verdict.match_type, Some(VerificationType::Partial));
+        assert_eq!(verdict.trailing_zeros_ignored, Some(2));
+
+        let verdict = match_bytecodes(&local, &onchain, &[], true, BytecodeHash::Ipfs, false);
+        assert_eq!(verdict.match_type, None);
+
+        // Non-zero trailing bytes are still a difference.
+        let onchain = [0x60, 0x80, 0x00, 0x01, 0x00];
+        let verdict = match_bytecodes(&local, &onchain, &[], true, BytecodeHash::Ipfs, true);
+        assert_eq!(verdict.match_type, None);
+    }
+
+    #[test]
+    fn test_json_result_diff() {
+        let verdict = MatchVerdict {
+            match_type: Some(VerificationType::Full),
+            body_match: true,
+            similarity: 1.0,
+            ..Default::default()
+        };
+        let details = VerificationDetails::default();
+        let expected =
+            JsonResult::new(Address::ZERO, BytecodeType::Runtime, Some(verdict), None, &details);
+        assert!(expected.diff(&expected).unwrap().is_empty());
+
+        let verdict = MatchVerdict { similarity: 0.5, ..Default::default() };
+        let details = VerificationDetails {
+            contract_path: Some("src/Counter.sol:Counter".to_string()),
+            ..Default::default()
+        };
+        let actual =
+            JsonResult::new(Address::ZERO, BytecodeType::Runtime, Some(verdict), None, &details);
+        let fields =
+            actual.diff(&expected).unwrap().into_iter().map(|diff| diff.field).collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            ["contract_path", "match_type", "verdict.body_match", "verdict.similarity", "verified"]
+        );
+    }
+
+    #[test]
+    fn test_verification_summary() {
+        let result = |match_type| {
+            let verdict = MatchVerdict { match_type, ..Default::default() };
+            JsonResult::new(
+                Address::ZERO,
+                BytecodeType::Creation,
+                Some(verdict),
+                None,
+                &VerificationDetails::default(),
+            )
+        };
+        let results = [
+            result(Some(VerificationType::Full)),
+            result(Some(VerificationType::Partial)),
+            result(Some(VerificationType::Full)),
+            result(None),
+        ];
+        assert_eq!(
+            VerificationSummary::new(&results),
+            VerificationSummary { total: 4, full: 2, partial: 1, none: 1, errored: 0 }
+        );
+
+        // A batch where some of the verifications fail before producing any result.
+        let mut summary = VerificationSummary::new(&results[..2]);
+        summary.add_error();
+        let mut diamond = VerificationSummary::new(&results[2..]);
+        diamond.add_error();
+        summary.merge(diamond);
+        assert_eq!(
+            summary,
+            VerificationSummary { total: 4, full: 2, partial: 1, none: 1, errored: 2 }
+        );
+    }
+
+    #[test]
+    fn test_quiet_verdict() {
+        let result = |bytecode_type, match_type| {
+            let verdict = MatchVerdict { match_type, ..Default::default() };
+            JsonResult::new(
+                Address::ZERO,
+                bytecode_type,
+                Some(verdict),
+                None,
+                &VerificationDetails::default(),
+            )
+        };
+        let full = [
+            result(BytecodeType::Creation, Some(VerificationType::Full)),
+            result(BytecodeType::Runtime, Some(VerificationType::Full)),
+        ];
+        assert_eq!(quiet_verdict(&full), "FULL MATCH");
+
+        let partial = [
+            result(BytecodeType::Creation, Some(VerificationType::Full)),
+            result(BytecodeType::Runtime, Some(VerificationType::Partial)),
+        ];
+        assert_eq!(quiet_verdict(&partial), "PARTIAL (runtime)");
+
+        let none = [
+            result(BytecodeType::Creation, Some(VerificationType::Partial)),
+            result(BytecodeType::Runtime, None),
+        ];
+        assert_eq!(quiet_verdict(&none), "NO MATCH");
+        assert_eq!(quiet_verdict(&[]), "NO MATCH");
+    }
+
+    #[test]
+    fn test_deployment_window_violation() {
+        assert_eq!(deployment_window_violation(100, None, None), None);
+        assert_eq!(deployment_window_violation(100, Some(99), Some(101)), None);
+        assert_eq!(
+            deployment_window_violation(100, Some(100), None).as_deref(),
+            Some("created at block 100, not after --deployed-after 100")
+        );
+        assert_eq!(
+            deployment_window_violation(100, None, Some(100)).as_deref(),
+            Some("created at block 100, not before --deployed-before 100")
+        );
+        // Predeploys are created at genesis.
+        assert!(deployment_window_violation(0, Some(0), None).is_some());
+        assert_eq!(deployment_window_violation(0, None, Some(1)), None);
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let result = |bytecode_type, match_type| {
+            let verdict = MatchVerdict { match_type, ..Default::default() };
+            JsonResult::new(
+                Address::ZERO,
+                bytecode_type,
+                Some(verdict),
+                None,
+                &VerificationDetails::default(),
+            )
+        };
+        let mut report = JunitReport::default();
+        report.add_results(
+            "Counter<1>",
+            Address::ZERO,
+            &[
+                result(BytecodeType::Creation, Some(VerificationType::Full)),
+                result(BytecodeType::Runtime, None),
+            ],
+        );
+        report.add_error("Token", Address::ZERO, &eyre::eyre!("no creation tx"));
+        let xml = report.to_xml();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(xml.contains("tests=\"3\" failures=\"1\" errors=\"1\""));
+        assert!(xml.contains("<testcase name=\"creation\" classname=\"Counter&lt;1&gt; (0x"));
+        assert!(xml.contains("<failure message=\"Runtime code did not match\">opcodes differ"));
+        assert!(xml.contains("<error message=\"no creation tx\">no creation tx</error>"));
+    }
+
+    #[test]
+    fn test_experimental_pragmas() {
+        let source = r#"
+            pragma solidity ^0.5.0;
+            pragma experimental ABIEncoderV2;
+            pragma experimental "SMTChecker";
+            // pragma experimental ABIEncoderV2;
+            contract A {}
+        "#;
+        assert_eq!(experimental_pragmas(source), ["ABIEncoderV2", "SMTChecker"]);
+
+        let source = r#"
+            pragma solidity ^0.5.0;
+            /* pragma experimental SMTChecker; */
+            /*
+             * pragma experimental "SMTChecker";
+             */
+            pragma /* enabled for the structs */ experimental ABIEncoderV2;
+            contract A {} // pragma experimental SMTChecker; /*
+        "#;
+        assert_eq!(experimental_pragmas(source), ["ABIEncoderV2"]);
+        assert!(experimental_pragmas("pragma solidity ^0.8.0; pragma abicoder v2;").is_empty());
+    }
+
+    #[test]
+    fn test_diff_runs() {
+        let run = |offset, local: &[u8], onchain: &[u8]| DiffRun {
+            offset,
+            local: Bytes::copy_from_slice(local),
+            onchain: Bytes::copy_from_slice(onchain),
+        };
+        assert!(diff_runs(&[1, 2, 3], &[1, 2, 3]).is_empty());
+        assert_eq!(
+            diff_runs(&[1, 2, 3, 4, 5], &[1, 9, 9, 4, 9]),
+            [run(1, &[2, 3], &[9, 9]), run(4, &[5], &[9])]
+        );
+
+        // The tail of the longer bytecode extends an adjacent run.
+        assert_eq!(diff_runs(&[1, 2], &[1, 9, 3, 4]), [run(1, &[2], &[9, 3, 4])]);
+        assert_eq!(diff_runs(&[1, 2, 3], &[1, 2]), [run(2, &[3], &[])]);
+    }
+
+    #[test]
+    fn test_certificate() {
+        let details = VerificationDetails {
+            creation_tx_hash: Some(B256::with_last_byte(1)),
+            onchain_code_hash: Some(B256::with_last_byte(2)),
+            local_code_hash: Some(B256::with_last_byte(2)),
+            ..Default::default()
+        };
+        let verdict =
+            MatchVerdict { match_type: Some(VerificationType::Full), ..Default::default() };
+        let results = [
+            JsonResult::new(Address::ZERO, BytecodeType::Creation, Some(verdict), None, &details),
+            JsonResult::new(Address::ZERO, BytecodeType::Runtime, Some(verdict), None, &details),
+        ];
+        // The code hashes are only reported with the runtime result.
+        assert_eq!(results[0].local_code_hash, None);
+
+        let certificate = Certificate::new(None, "Counter", &results, false).unwrap();
+        assert_eq!(certificate.creation_tx_hash, details.creation_tx_hash);
+        assert_eq!(certificate.onchain_code_hash, details.onchain_code_hash);
+        assert_eq!(certificate.local_code_hash, details.local_code_hash);
+        assert_eq!(certificate.verdict, None);
+
+        let certificate = Certificate::new(None, "Counter", &results, true).unwrap();
+        assert_eq!(certificate.verdict.as_deref(), Some("FULL MATCH"));
+        assert!(Certificate::new(None, "Counter", &[], false).is_err());
+    }
+
+    #[test]
+    fn test_broadcast_contracts() {
+        let broadcast: Broadcast = serde_json::from_str(
+            r#"{
+                "transactions": [
+                    {
+                        "transactionType": "CREATE",
+                        "contractName": "Counter",
+                        "contractAddress": "0x00000000000000000000000000000000000000aa",
+                        "arguments": ["1"]
+                    },
+                    {
+                        "transactionType": "CALL",
+                        "contractName": "Counter",
+                        "contractAddress": "0x00000000000000000000000000000000000000aa",
+                        "arguments": null
+                    },
+                    {
+                        "transactionType": "CREATE2",
+                        "contractName": "Token",
+                        "contractAddress": "0x00000000000000000000000000000000000000bb",
+                        "arguments": null
+                    },
+                    {
+                        "transactionType": "CREATE",
+                        "contractName": null,
+                        "contractAddress": "0x00000000000000000000000000000000000000cc",
+                        "arguments": null
+                    }
+                ],
+                "chain": 1
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            broadcast.contracts(),
+            [
+                BroadcastContract {
+                    address: Address::with_last_byte(0xaa),
+                    name: "Counter".to_string(),
+                    arguments: Some(vec!["1".to_string()]),
+                },
+                BroadcastContract {
+                    address: Address::with_last_byte(0xbb),
+                    name: "Token".to_string(),
+                    arguments: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_to_ledger() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger").join("verifications.jsonl");
+        let details = VerificationDetails {
+            local_solc_version: Some("0.8.26".to_string()),
+            ..Default::default()
+        };
+        let result = JsonResult::new(Address::ZERO, BytecodeType::Runtime, None, None, &details);
+
+        append_to_ledger(&path, Some(Chain::mainnet()), &[result.clone()]).unwrap();
+        append_to_ledger(&path, None, &[result]).unwrap();
+
+        let ledger = std::fs::read_to_string(&path).unwrap();
+        let entries = ledger
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["compiler"], "0.8.26");
+        assert_eq!(entries[0]["bytecode_type"], "runtime");
+        assert!(entries[1]["chain"].is_null());
+    }
+
+    #[test]
+    fn test_mark_verified() {
+        let result = |match_type| {
+            let verdict = MatchVerdict { match_type, ..Default::default() };
+            JsonResult::new(
+                Address::ZERO,
+                BytecodeType::Creation,
+                Some(verdict),
+                None,
+                &VerificationDetails::default(),
+            )
+        };
+
+        let mut results = vec![result(Some(VerificationType::Full))];
+        assert!(results[0].verified);
+        results.push(result(Some(VerificationType::Partial)));
+        mark_verified(&mut results);
+        assert!(results.iter().all(|res| !res.verified));
+
+        let mut results =
+            vec![result(Some(VerificationType::Full)), result(Some(VerificationType::Full))];
+        mark_verified(&mut results);
+        assert!(results.iter().all(|res| res.verified));
+    }
+
+    #[test]
+    fn test_extract_archive() {
+        assert!(archive_entry_path(Path::new("src/Counter.sol")).is_ok());
+        assert!(archive_entry_path(Path::new("./foundry.toml")).is_ok());
+        assert!(archive_entry_path(Path::new("../foundry.toml")).is_err());
+        assert!(archive_entry_path(Path::new("src/../../foundry.toml")).is_err());
+        assert!(archive_entry_path(Path::new("/etc/foundry.toml")).is_err());
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, "project/foundry.toml", &b"[a]"[..]).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        extract_archive("bundle.tar", &archive, dir.path()).unwrap();
+        assert_eq!(std::fs::read(dir.path().join("project/foundry.toml")).unwrap(), b"[a]");
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference(&[0x60, 0x80], &[0x60, 0x80]), None);
+        assert_eq!(first_difference(&[0x60, 0x80], &[0x60, 0x81]), Some(1));
+        assert_eq!(first_difference(&[0x60], &[0x60, 0x80]), Some(1));
+    }
+
+    #[test]
+    fn test_extract_solc_version() {
+        // `{"solc": h'000815'}` ++ metadata length
+        let mut bytecode =
+            vec![0x60, 0x80, 0xa1, 0x64, b's', b'o', b'l', b'c', 0x43, 0x00, 0x08, 0x15];
+        bytecode.extend_from_slice(&[0x00, 0x0a]);
+        assert_eq!(extract_solc_version(&bytecode), Some(Version::new(0, 8, 21)));
+
+        assert_eq!(extract_solc_version(&[0x60, 0x80]), None);
+    }
+
+    #[test]
+    fn test_extract_metadata_digest() {
+        // `{"bzzr0": h'<32 bytes>'}` ++ metadata length
+        let mut bytecode = vec![0x60, 0x80, 0xa1, 0x65, b'b', b'z', b'z', b'r', b'0', 0x58, 0x20];
+        bytecode.extend_from_slice(&[0xab; 32]);
+        bytecode.extend_from_slice(&[0x00, 0x29]);
+        let digest = extract_metadata_digest(&bytecode).unwrap();
+        assert_eq!(digest.digest_type, MetadataDigestType::Bzzr0);
+        assert_eq!(digest.digest, Bytes::from(vec![0xab; 32]));
+        assert_eq!(extract_solc_version(&bytecode), None);
+
+        // `{"solc": h'000815'}` ++ metadata length
+        let mut bytecode =
+            vec![0x60, 0x80, 0xa1, 0x64, b's', b'o', b'l', b'c', 0x43, 0x00, 0x08, 0x15];
+        bytecode.extend_from_slice(&[0x00, 0x0a]);
+        assert_eq!(extract_metadata_digest(&bytecode), None);
+    }
+
+    #[test]
+    fn test_extract_metadata_hash() {
+        // `{"ipfs": h'<34 bytes>', "experimental": true, "solc": h'00081a'}` ++ metadata length
+        let mut metadata = vec![0xa3, 0x64, b'i', b'p', b'f', b's', 0x58, 0x22];
+        metadata.extend_from_slice(&[0x12; 34]);
+        metadata.push(0x6c);
+        metadata.extend_from_slice(b"experimental");
+        metadata.extend_from_slice(&[0xf5, 0x64, b's', b'o', b'l', b'c', 0x43, 0x00, 0x08, 0x1a]);
+        metadata.extend_from_slice(&(metadata.len() as u16).to_be_bytes());
+        let bytecode = [&[0x60, 0x80][..], &metadata].concat();
+        assert_eq!(metadata_len(&bytecode), Some(metadata.len()));
+        assert_eq!(extract_metadata_hash(&bytecode), [0x60, 0x80]);
+        assert_eq!(extract_solc_version(&bytecode), Some(Version::new(0, 8, 26)));
+
+        // A tail which decodes to a CBOR integer, or to a map of unknown entries.
+        assert_eq!(extract_metadata_hash(&[0x60, 0x01, 0x00, 0x01]), [0x60, 0x01, 0x00, 0x01]);
+        let bytecode = [0x60, 0xa1, 0x61, b'a', 0x01, 0x00, 0x04];
+        assert_eq!(extract_metadata_hash(&bytecode), bytecode);
+        // Trailing bytes after the CBOR value.
+        let bytecode = [0x60, 0xa0, 0x01, 0x00, 0x02];
+        assert_eq!(extract_metadata_hash(&bytecode), bytecode);
+        assert_eq!(extract_metadata_hash(&[0x60]), [0x60]);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(&[], &[]), 1.0);
+        assert_eq!(similarity(&[0x60, 0x80], &[0x60, 0x80]), 1.0);
+        assert_eq!(similarity(&[0x60, 0x80, 0x01, 0x02], &[0x60, 0x81, 0x01]), 0.5);
+    }
+
+    #[test]
+    fn test_is_top_level_creation() {
+        let parent_code = [0x60, 0x80, 0x01];
+        let child_code = [0x60, 0x80, 0x02];
+        let create =
+            Transaction { input: Bytes::copy_from_slice(&parent_code), ..Default::default() };
+        assert!(is_top_level_creation(&create, &parent_code, None));
+        // The child is deployed by the constructor of the contract created by the tx.
+        assert!(!is_top_level_creation(&create, &child_code, None));
+
+        let input = [[0x11; 32].as_slice(), &child_code].concat();
+        let factory_call = Transaction {
+            to: Some(DEFAULT_CREATE2_DEPLOYER),
+            input: input.into(),
+            ..Default::default()
+        };
+        assert!(is_top_level_creation(&factory_call, &child_code, None));
+        assert!(!is_top_level_creation(&factory_call, &parent_code, None));
+
+        // A contract created by a call to any other contract is always nested.
+        let call = Transaction {
+            to: Some(Address::with_last_byte(1)),
+            input: Bytes::copy_from_slice(&child_code),
+            ..Default::default()
+        };
+        assert!(!is_top_level_creation(&call, &child_code, None));
+    }
+
+    #[test]
+    fn test_create2_layout() {
+        let input = [[0x11; 32].as_slice(), &[0x60, 0x80]].concat();
+        let layout = Create2Layout::default();
+        assert_eq!(layout.initcode(&input).unwrap(), [0x60, 0x80]);
+        assert_eq!(
+            layout.replace_initcode(&input, &[0x01]).unwrap(),
+            Bytes::from([[0x11; 32].as_slice(), &[0x01]].concat())
+        );
+        assert!(layout.initcode(&input[..16]).is_err());
+        assert_eq!(
+            layout.replace_salt(&input, B256::repeat_byte(0x22)).unwrap(),
+            Bytes::from([[0x22; 32].as_slice(), &[0x60, 0x80]].concat())
+        );
+
+        // selector ++ salt ++ initcode
+        let input = [[0xaa; 4].as_slice(), &[0x11; 32], &[0x60, 0x80]].concat();
+        let layout = Create2Layout { salt_offset: 4, initcode_offset: 36 };
+        assert_eq!(layout.initcode(&input).unwrap(), [0x60, 0x80]);
+        assert!(Create2Layout { salt_offset: 4, initcode_offset: 32 }.initcode(&input).is_err());
+
+        let factory = Address::with_last_byte(1);
+        assert_eq!(Create2Layout::resolve(Some(factory), None), None);
+        assert_eq!(Create2Layout::resolve(Some(factory), Some(layout)), Some(layout));
+        assert_eq!(
+            Create2Layout::resolve(Some(DEFAULT_CREATE2_DEPLOYER), None),
+            Some(Create2Layout::default())
+        );
+        assert_eq!(Create2Layout::resolve(None, Some(layout)), None);
+    }
+
+    #[test]
+    fn test_apply_link_args() {
+        let mut args = vec!["1".to_string(), "0x00".to_string()];
+        let address = Address::with_last_byte(0x42);
+        let link_arg = "1:0x0000000000000000000000000000000000000042".parse().unwrap();
+        apply_link_args(&mut args, &[link_arg]).unwrap();
+        assert_eq!(args, ["1".to_string(), address.to_string()]);
+
+        assert!(apply_link_args(&mut args, &[LinkArg { index: 2, address }]).is_err());
+    }
+
+    #[test]
+    fn test_check_immutables() {
+        let owner = Address::with_last_byte(0xaa);
+        let mut code = vec![0xff; 70];
+        code[6..38].copy_from_slice(owner.into_word().as_slice());
+        code[38..70].copy_from_slice(B256::with_last_byte(1).as_slice());
+        let immutable_references = BTreeMap::from([
+            ("7".to_string(), vec![Offsets { start: 6, length: 32 }]),
+            (
+                "9".to_string(),
+                vec![Offsets { start: 38, length: 32 }, Offsets { start: 0, length: 2 }],
+            ),
+        ]);
+
+        let expected = [
+            "7=0x00000000000000000000000000000000000000aa".parse().unwrap(),
+            "9=1".parse().unwrap(),
+        ];
+        let checks = check_immutables(&code, &immutable_references, &expected).unwrap();
+        assert!(checks[0].matches);
+        // The second reference of `9` holds a different value.
+        assert!(!checks[1].matches);
+        assert_eq!(
+            checks[1].actual,
+            [B256::with_last_byte(1), B256::left_padding_from(&[0xff, 0xff])]
+        );
+
+        let unknown: ExpectedImmutable = "8=1".parse().unwrap();
+        assert!(check_immutables(&code, &immutable_references, &[unknown]).is_err());
+    }
+
+    #[test]
+    fn test_compiler_candidates() {
+        let versions = [Version::new(0, 8, 19), Version::new(0, 8, 20)];
+        let candidates = compiler_candidates(&versions, &[], 200, true, EvmVersion::Paris);
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.iter().all(|candidate| candidate.optimizer_runs == 200));
+
+        let candidates = compiler_candidates(&versions, &[1, 10_000], 200, true, EvmVersion::Paris);
+        let settings = candidates
+            .iter()
+            .map(|candidate| {
+                (candidate.version.minor, candidate.version.patch, candidate.optimizer_runs)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(settings, [(8, 19, 1), (8, 19, 10_000), (8, 20, 1), (8, 20, 10_000)]);
+    }
+
+    #[test]
+    fn test_optimizer_candidates() {
+        let candidates = optimizer_candidates(Version::new(0, 8, 20), 200, EvmVersion::Paris);
+        let settings = candidates.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            settings,
+            ["solc 0.8.20, 200 runs, paris", "solc 0.8.20, optimizer disabled, paris"]
+        );
+
+        assert_eq!(
+            parse_explorer_compiler_version("v0.8.20+commit.a1b79de6").unwrap(),
+            Version::new(0, 8, 20)
+        );
+        assert!(parse_explorer_compiler_version("vyper:0.3.10").is_err());
+    }
+
+    #[test]
+    fn test_linked_bytes() {
+        let placeholder = format!("__${}$__", "0".repeat(34));
+        let bytecode: CompactBytecode = serde_json::from_value(serde_json::json!({
+            "object": format!("60{placeholder}00{placeholder}"),
+            "linkReferences": {
+                "src/Lib.sol": { "Lib": [{ "start": 1, "length": 20 }, { "start": 22, "length": 20 }] }
+            }
+        }))
+        .unwrap();
+        let err = linked_bytes(Some(&bytecode)).unwrap_err().to_string();
+        assert!(err.ends_with("src/Lib.sol:Lib at offsets 1, 22"), "{err}");
+
+        let bytecode: CompactBytecode =
+            serde_json::from_value(serde_json::json!({ "object": "0x6000", "linkReferences": {} }))
+                .unwrap();
+        assert_eq!(linked_bytes(Some(&bytecode)).unwrap(), Bytes::from_static(&[0x60, 0x00]));
+    }
+
+    #[test]
+    fn test_parse_explorer_libraries() {
+        let libraries = parse_explorer_libraries(
+            "SafeMath:0x00000000000000000000000000000000000000aa; Strings:00000000000000000000000000000000000000bb",
+        )
+        .unwrap();
+        assert_eq!(
+            libraries,
+            BTreeMap::from([
+                ("SafeMath".to_string(), Address::with_last_byte(0xaa)),
+                ("Strings".to_string(), Address::with_last_byte(0xbb)),
+            ])
+        );
+        assert!(parse_explorer_libraries("").unwrap().is_empty());
+        assert!(parse_explorer_libraries("SafeMath").is_err());
+    }
+
+    #[test]
+    fn test_mask_linked_libraries() {
+        let lib = Address::repeat_byte(0x11);
+        let push = |address: Address| [&[0x73][..], address.as_slice(), &[0x5b]].concat();
+        let references = BTreeMap::from([("Lib".to_string(), vec![1])]);
+        let linked = BTreeMap::from([("Lib".to_string(), lib)]);
+
+        let mut local = push(lib);
+        let mut onchain = push(lib);
+        let checks = mask_linked_libraries(&mut local, &mut onchain, &references, &linked);
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].matches);
+        assert_eq!(checks[0].actual, vec![lib]);
+        assert_eq!(local, push(Address::ZERO));
+        assert_eq!(local, onchain);
+
+        // The on-chain code calls another deployment of the library.
+        let mut local = push(lib);
+        let mut onchain = push(Address::repeat_byte(0x22));
+        let checks = mask_linked_libraries(&mut local, &mut onchain, &references, &linked);
+        assert!(!checks[0].matches);
+        assert_eq!(checks[0].actual, vec![Address::repeat_byte(0x22)]);
+        assert_eq!(local, onchain);
+
+        let mut short = vec![0x73];
+        let checks = mask_linked_libraries(&mut local, &mut short, &references, &linked);
+        assert!(!checks[0].matches);
+        assert!(mask_linked_libraries(&mut local, &mut onchain, &references, &BTreeMap::new())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_mask_immutables() {
+        let mut bytecode = vec![0xff; 8];
+        let immutable_references = BTreeMap::from([(
+            "1".to_string(),
+            vec![Offsets { start: 1, length: 2 }, Offsets { start: 7, length: 4 }],
+        )]);
+        mask_immutables(&mut bytecode, &immutable_references);
+        // Out of bounds references are ignored.
+        assert_eq!(bytecode, [0xff, 0, 0, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_decode_facets() {
+        let facet = |address: Address, selectors: &[[u8; 4]]| {
+            DynSolValue::Tuple(vec![
+                DynSolValue::Address(address),
+                DynSolValue::Array(
+                    selectors
+                        .iter()
+                        .map(|selector| {
+                            DynSolValue::FixedBytes(B256::right_padding_from(selector), 4)
+                        })
+                        .collect(),
+                ),
+            ])
+        };
+        let data = DynSolValue::Array(vec![
+            facet(Address::repeat_byte(1), &[[0x01, 0xff, 0xc9, 0xa7], [0x7a, 0x0e, 0xd6, 0x27]]),
+            facet(Address::repeat_byte(2), &[]),
+        ])
+        .abi_encode();
+
+        let facets = decode_facets(&data).unwrap();
+        assert_eq!(
+            facets,
+            [
+                Facet {
+                    address: Address::repeat_byte(1),
+                    selectors: vec![
+                        Selector::from([0x01, 0xff, 0xc9, 0xa7]),
+                        Selector::from([0x7a, 0x0e, 0xd6, 0x27])
+                    ],
+                },
+                Facet { address: Address::repeat_byte(2), selectors: vec![] },
+            ]
+        );
+        assert!(decode_facets(&[0x01]).is_err());
+    }
+
+    #[test]
+    fn test_mask_ranges() {
+        let ranges: Vec<MaskRange> =
+            ["1:2", "6:4", "9:1"].iter().map(|range| range.parse().unwrap()).collect();
+        assert_eq!(ranges[1], MaskRange { start: 6, len: 4 });
+        assert!("1".parse::<MaskRange>().is_err());
+
+        let mut bytecode = vec![0xff; 8];
+        mask_ranges(&mut bytecode, &ranges);
+        // Ranges exceeding the bytecode are masked up to its end.
+        assert_eq!(bytecode, [0xff, 0, 0, 0xff, 0xff, 0xff, 0, 0]);
+    }
+
+    #[test]
+    fn test_mask_library_address() {
+        let runtime_code = |address: Address| {
+            [&[0x73][..], address.as_slice(), &[0x30, 0x14, 0x60, 0x80]].concat()
+        };
+        let artifact = runtime_code(Address::ZERO);
+        let mut local = runtime_code(Address::with_last_byte(1));
+        let mut onchain = runtime_code(Address::with_last_byte(2));
+        assert!(mask_library_address(&artifact, &mut local, &mut onchain));
+        assert_eq!(local, artifact);
+        assert_eq!(onchain, artifact);
+
+        // Contracts which merely start with a `PUSH20` aren't libraries.
+        let mut local = runtime_code(Address::with_last_byte(1));
+        let mut onchain = runtime_code(Address::with_last_byte(2));
+        assert!(!mask_library_address(&local.clone(), &mut local, &mut onchain));
+        assert!(!mask_library_address(&artifact, &mut [0x60, 0x80], &mut onchain));
+    }
 }
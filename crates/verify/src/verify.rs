@@ -16,7 +16,10 @@ use foundry_cli::{
 };
 use foundry_common::{compile::ProjectCompiler, ContractsByArtifact};
 use foundry_compilers::{artifacts::EvmVersion, compilers::solc::Solc, info::ContractInfo};
-use foundry_config::{figment, impl_figment_convert, impl_figment_convert_cast, Config, SolcReq};
+use foundry_config::{
+    figment, impl_figment_convert, impl_figment_convert_cast, Config, ResolvedEtherscanConfig,
+    SolcReq,
+};
 use itertools::Itertools;
 use reqwest::Url;
 use revm_primitives::HashSet;
@@ -28,8 +31,10 @@ use crate::provider::VerificationContext;
 #[derive(Clone, Debug, Parser)]
 pub struct VerifierArgs {
     /// The contract verification provider to use.
-    #[arg(long, help_heading = "Verifier options", default_value = "etherscan", value_enum)]
-    pub verifier: VerificationProviderType,
+    ///
+    /// Defaults to the `verifier` of the chain's `[etherscan]` config section, or to `etherscan`.
+    #[arg(long, help_heading = "Verifier options", value_enum)]
+    pub verifier: Option<VerificationProviderType>,
 
     /// The verifier URL, if using a custom provider
     #[arg(long, help_heading = "Verifier options", env = "VERIFIER_URL")]
@@ -38,7 +43,31 @@ pub struct VerifierArgs {
 
 impl Default for VerifierArgs {
     fn default() -> Self {
-        Self { verifier: VerificationProviderType::Etherscan, verifier_url: None }
+        Self { verifier: None, verifier_url: None }
+    }
+}
+
+impl VerifierArgs {
+    /// Returns the verification provider passed with `--verifier`, or the default one.
+    pub fn verifier(&self) -> VerificationProviderType {
+        self.verifier.clone().unwrap_or_default()
+    }
+
+    /// Uses the verifier configured in the chain's `[etherscan]` section, unless one was passed
+    /// with `--verifier`.
+    ///
+    /// The section's `url` is already used as the API URL if `--verifier-url` is not set.
+    pub fn apply_etherscan_config(
+        &mut self,
+        etherscan_config: Option<&ResolvedEtherscanConfig>,
+    ) -> Result<()> {
+        let Some(verifier) = etherscan_config.and_then(|config| config.verifier.as_deref()) else {
+            return Ok(())
+        };
+        if self.verifier.is_none() {
+            self.verifier = Some(verifier.parse().map_err(|err: String| eyre::eyre!(err))?);
+        }
+        Ok(())
     }
 }
 
@@ -190,8 +219,10 @@ impl VerifyArgs {
         let context = self.resolve_context().await?;
 
         // Set Etherscan options.
+        let etherscan_config = config.get_etherscan_config_with_chain(Some(chain))?;
+        self.verifier.apply_etherscan_config(etherscan_config.as_ref())?;
         self.etherscan.chain = Some(chain);
-        self.etherscan.key = config.get_etherscan_config_with_chain(Some(chain))?.map(|c| c.key);
+        self.etherscan.key = etherscan_config.map(|c| c.key);
 
         if self.show_standard_json_input {
             let args = EtherscanVerificationProvider::default()
@@ -203,7 +234,7 @@ impl VerifyArgs {
 
         let verifier_url = self.verifier.verifier_url.clone();
         println!("Start verifying contract `{}` deployed on {chain}", self.address);
-        self.verifier.verifier.client(&self.etherscan.key())?.verify(self, context).await.map_err(|err| {
+        self.verifier.verifier().client(&self.etherscan.key())?.verify(self, context).await.map_err(|err| {
             if let Some(verifier_url) = verifier_url {
                  match Url::parse(&verifier_url) {
                     Ok(url) => {
@@ -227,7 +258,7 @@ impl VerifyArgs {
 
     /// Returns the configured verification provider
     pub fn verification_provider(&self) -> Result<Box<dyn VerificationProvider>> {
-        self.verifier.verifier.client(&self.etherscan.key())
+        self.verifier.verifier().client(&self.etherscan.key())
     }
 
     /// Resolves [VerificationContext] object either from entered contract name or by trying to
@@ -334,7 +365,7 @@ impl VerifyCheckArgs {
     /// Run the verify command to submit the contract's source code for verification on etherscan
     pub async fn run(self) -> Result<()> {
         println!("Checking verification status on {}", self.etherscan.chain.unwrap_or_default());
-        self.verifier.verifier.client(&self.etherscan.key())?.check(self).await
+        self.verifier.verifier().client(&self.etherscan.key())?.check(self).await
     }
 }
 
@@ -364,4 +395,29 @@ mod tests {
         ]);
         assert!(args.via_ir);
     }
+
+    #[test]
+    fn can_apply_etherscan_config_verifier() {
+        let etherscan_config = ResolvedEtherscanConfig {
+            api_url: "https://explorer.example/api".to_string(),
+            browser_url: None,
+            key: String::new(),
+            chain: None,
+            verifier: Some("blockscout".to_string()),
+        };
+        let parse = |args: &[&str]| {
+            let address = "0x0000000000000000000000000000000000000000";
+            VerifyArgs::parse_from(["foundry-cli", address].into_iter().chain(args.iter().copied()))
+                .verifier
+        };
+
+        let mut verifier = parse(&[]);
+        verifier.apply_etherscan_config(Some(&etherscan_config)).unwrap();
+        assert_eq!(verifier.verifier(), VerificationProviderType::Blockscout);
+
+        // An explicit `--verifier etherscan` is kept, even though it's the default one.
+        let mut verifier = parse(&["--verifier", "etherscan"]);
+        verifier.apply_etherscan_config(Some(&etherscan_config)).unwrap();
+        assert_eq!(verifier.verifier(), VerificationProviderType::Etherscan);
+    }
 }